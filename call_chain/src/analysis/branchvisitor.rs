@@ -0,0 +1,453 @@
+use rustc_hir::{self, Arm, Block, Body, Closure, Expr, ExprKind, LetStmt, MatchSource, StmtKind};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use super::exporter::{Cond, CondChain};
+
+const MAX_CHAINS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ChainState {
+    Live,
+    Broken,
+    Returned,
+}
+
+#[derive(Debug, Clone)]
+struct PartialChain {
+    conds: Vec<Cond>,
+    path: Vec<usize>,
+    ret: Option<String>,
+    state: ChainState,
+}
+
+pub struct BranchVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    fn_name: String,
+    truncated: bool,
+}
+
+impl<'tcx> BranchVisitor<'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>, fn_name: String) -> Self {
+        BranchVisitor {
+            tcx,
+            fn_name,
+            truncated: false,
+        }
+    }
+
+    pub fn get_cond_chains(&mut self, body: &'tcx Body<'tcx>) -> Vec<CondChain> {
+        let start = PartialChain {
+            conds: Vec::new(),
+            path: Vec::new(),
+            ret: None,
+            state: ChainState::Live,
+        };
+        let chains = self.visit_expr(body.value, vec![start]);
+        if self.truncated {
+            warn!(
+                "Too many condition chains in {}, only {} are kept",
+                self.fn_name, MAX_CHAINS
+            );
+        }
+        let mut cond_chains: Vec<CondChain> = Vec::new();
+        for chain in chains {
+            let mut cond_chain = CondChain::new(chain.conds, chain.path, chain.ret);
+            cond_chain.set_may_contra();
+            cond_chains.push(cond_chain);
+        }
+        cond_chains
+    }
+
+    fn get_snippet(&self, span: Span) -> String {
+        let span = span.source_callsite();
+        self.tcx
+            .sess
+            .source_map()
+            .span_to_snippet(span)
+            .unwrap_or_default()
+    }
+
+    fn get_line(&self, span: Span) -> usize {
+        let span = span.source_callsite();
+        self.tcx.sess.source_map().lookup_char_pos(span.lo()).line
+    }
+
+    // the first line executed in a branch, so that it can be matched with line coverage
+    fn get_entry_line(&self, block: &'tcx Block<'tcx>) -> usize {
+        if let Some(stmt) = block.stmts.first() {
+            self.get_line(stmt.span)
+        } else if let Some(expr) = block.expr {
+            self.get_line(expr.span)
+        } else {
+            self.get_line(block.span)
+        }
+    }
+
+    // splits every live chain into one chain per branch, chains that already
+    // returned or broke out of a loop are kept as they are
+    fn split_chains(
+        &mut self,
+        chains: Vec<PartialChain>,
+        branches: &[(Cond, Option<&'tcx Expr<'tcx>>)],
+    ) -> Vec<PartialChain> {
+        let mut result: Vec<PartialChain> = Vec::new();
+        let mut live: Vec<PartialChain> = Vec::new();
+        for chain in chains {
+            if chain.state == ChainState::Live {
+                live.push(chain);
+            } else {
+                result.push(chain);
+            }
+        }
+        for chain in live {
+            for (cond, branch) in branches.iter() {
+                if result.len() >= MAX_CHAINS {
+                    self.truncated = true;
+                    return result;
+                }
+                let mut new_chain = chain.clone();
+                new_chain.conds.push(cond.clone());
+                match branch {
+                    Some(expr) => {
+                        let line = match expr.kind {
+                            ExprKind::Block(block, _) => self.get_entry_line(block),
+                            _ => self.get_line(expr.span),
+                        };
+                        new_chain.path.push(line);
+                        result.extend(self.visit_expr(expr, vec![new_chain]));
+                    }
+                    None => result.push(new_chain),
+                }
+            }
+        }
+        result.truncate(MAX_CHAINS);
+        result
+    }
+
+    fn visit_block(
+        &mut self,
+        block: &'tcx Block<'tcx>,
+        mut chains: Vec<PartialChain>,
+    ) -> Vec<PartialChain> {
+        for stmt in block.stmts.iter() {
+            chains = match stmt.kind {
+                StmtKind::Let(let_stmt) => self.visit_let_stmt(let_stmt, chains),
+                StmtKind::Expr(expr) | StmtKind::Semi(expr) => self.visit_expr(expr, chains),
+                StmtKind::Item(_) => chains,
+            };
+        }
+        if let Some(expr) = block.expr {
+            chains = self.visit_expr(expr, chains);
+        }
+        chains
+    }
+
+    fn visit_let_stmt(
+        &mut self,
+        let_stmt: &'tcx LetStmt<'tcx>,
+        mut chains: Vec<PartialChain>,
+    ) -> Vec<PartialChain> {
+        if let Some(init) = let_stmt.init {
+            chains = self.visit_expr(init, chains);
+        }
+        if let (Some(init), Some(els)) = (let_stmt.init, let_stmt.els) {
+            let cond = format!(
+                "let {} = {}",
+                self.get_snippet(let_stmt.pat.span),
+                self.get_snippet(init.span)
+            );
+            let line = self.get_line(let_stmt.span);
+            let mut result: Vec<PartialChain> = Vec::new();
+            for chain in chains {
+                if chain.state != ChainState::Live {
+                    result.push(chain);
+                    continue;
+                }
+                if result.len() + 2 > MAX_CHAINS {
+                    self.truncated = true;
+                    break;
+                }
+                let mut matched = chain.clone();
+                matched
+                    .conds
+                    .push(Cond::new(cond.clone(), "true".to_string(), line));
+                result.push(matched);
+                let mut not_matched = chain;
+                not_matched
+                    .conds
+                    .push(Cond::new(cond.clone(), "false".to_string(), line));
+                not_matched.path.push(self.get_entry_line(els));
+                result.extend(self.visit_block(els, vec![not_matched]));
+            }
+            result.truncate(MAX_CHAINS);
+            return result;
+        }
+        chains
+    }
+
+    fn visit_arms(
+        &mut self,
+        cond: &str,
+        line: usize,
+        arms: &'tcx [Arm<'tcx>],
+        chains: Vec<PartialChain>,
+    ) -> Vec<PartialChain> {
+        let mut branches: Vec<(Cond, Option<&'tcx Expr<'tcx>>)> = Vec::new();
+        for arm in arms.iter() {
+            let mut value = self.get_snippet(arm.pat.span);
+            if let Some(guard) = arm.guard {
+                value += &format!(" if {}", self.get_snippet(guard.span));
+            }
+            branches.push((Cond::new(cond.to_string(), value, line), Some(arm.body)));
+        }
+        self.split_chains(chains, &branches)
+    }
+
+    fn visit_exprs(
+        &mut self,
+        exprs: &'tcx [Expr<'tcx>],
+        mut chains: Vec<PartialChain>,
+    ) -> Vec<PartialChain> {
+        for expr in exprs.iter() {
+            chains = self.visit_expr(expr, chains);
+        }
+        chains
+    }
+
+    // a closure body is walked where the closure is written, a `return` in it only
+    // leaves the closure, so the chains stay live
+    fn visit_closure(
+        &mut self,
+        closure: &'tcx Closure<'tcx>,
+        chains: Vec<PartialChain>,
+    ) -> Vec<PartialChain> {
+        let body = self.tcx.hir().body(closure.body);
+        let mut result: Vec<PartialChain> = Vec::new();
+        for chain in chains {
+            if chain.state != ChainState::Live {
+                result.push(chain);
+                continue;
+            }
+            let ret = chain.ret.clone();
+            for mut closure_chain in self.visit_expr(body.value, vec![chain]) {
+                closure_chain.state = ChainState::Live;
+                closure_chain.ret = ret.clone();
+                result.push(closure_chain);
+            }
+        }
+        result.truncate(MAX_CHAINS);
+        result
+    }
+
+    fn visit_expr(
+        &mut self,
+        expr: &'tcx Expr<'tcx>,
+        chains: Vec<PartialChain>,
+    ) -> Vec<PartialChain> {
+        match expr.kind {
+            ExprKind::Block(block, _) => self.visit_block(block, chains),
+            ExprKind::DropTemps(inner) => self.visit_expr(inner, chains),
+            ExprKind::If(cond, then, els) => {
+                let chains = match cond.kind {
+                    ExprKind::Let(let_expr) => self.visit_expr(let_expr.init, chains),
+                    _ => self.visit_expr(cond, chains),
+                };
+                let cond_str = self.get_snippet(cond.span);
+                let line = self.get_line(cond.span);
+                let branches = vec![
+                    (
+                        Cond::new(cond_str.clone(), "true".to_string(), line),
+                        Some(then),
+                    ),
+                    (Cond::new(cond_str, "false".to_string(), line), els),
+                ];
+                self.split_chains(chains, &branches)
+            }
+            ExprKind::Match(scrutinee, arms, source) => match source {
+                MatchSource::TryDesugar(_) => {
+                    let chains = self.visit_expr(scrutinee, chains);
+                    let cond = self.get_snippet(expr.span);
+                    let line = self.get_line(expr.span);
+                    let mut branches: Vec<(Cond, Option<&'tcx Expr<'tcx>>)> = Vec::new();
+                    for arm in arms.iter() {
+                        let value = match arm.body.kind {
+                            ExprKind::Ret(_) => "Break",
+                            _ => "Continue",
+                        };
+                        branches.push((
+                            Cond::new(cond.clone(), value.to_string(), line),
+                            Some(arm.body),
+                        ));
+                    }
+                    self.split_chains(chains, &branches)
+                }
+                MatchSource::ForLoopDesugar if arms.len() == 1 => {
+                    let chains = self.visit_expr(scrutinee, chains);
+                    self.visit_expr(arms[0].body, chains)
+                }
+                MatchSource::ForLoopDesugar => {
+                    let cond = format!("{} has next", self.get_snippet(scrutinee.span));
+                    let line = self.get_line(scrutinee.span);
+                    let mut branches: Vec<(Cond, Option<&'tcx Expr<'tcx>>)> = Vec::new();
+                    for (index, arm) in arms.iter().enumerate() {
+                        let value = if index == 0 { "false" } else { "true" };
+                        branches.push((
+                            Cond::new(cond.clone(), value.to_string(), line),
+                            Some(arm.body),
+                        ));
+                    }
+                    self.split_chains(chains, &branches)
+                }
+                MatchSource::Normal | MatchSource::Postfix => {
+                    let chains = self.visit_expr(scrutinee, chains);
+                    let cond = self.get_snippet(scrutinee.span);
+                    let line = self.get_line(scrutinee.span);
+                    self.visit_arms(&cond, line, arms, chains)
+                }
+                _ => self.visit_expr(scrutinee, chains),
+            },
+            ExprKind::Loop(block, _, _, _) => {
+                // loops are approximated by a single iteration
+                let mut chains = self.visit_block(block, chains);
+                for chain in chains.iter_mut() {
+                    if chain.state == ChainState::Broken {
+                        chain.state = ChainState::Live;
+                    }
+                }
+                chains
+            }
+            ExprKind::Break(_, value) => {
+                let mut chains = match value {
+                    Some(value) => self.visit_expr(value, chains),
+                    None => chains,
+                };
+                for chain in chains.iter_mut() {
+                    if chain.state == ChainState::Live {
+                        chain.state = ChainState::Broken;
+                    }
+                }
+                chains
+            }
+            ExprKind::Continue(_) => {
+                let mut chains = chains;
+                for chain in chains.iter_mut() {
+                    if chain.state == ChainState::Live {
+                        chain.state = ChainState::Broken;
+                    }
+                }
+                chains
+            }
+            ExprKind::Ret(value) => {
+                let mut chains = match value {
+                    Some(value) => self.visit_expr(value, chains),
+                    None => chains,
+                };
+                let ret = match value {
+                    Some(value) => self.get_snippet(value.span),
+                    None => "()".to_string(),
+                };
+                for chain in chains.iter_mut() {
+                    if chain.state == ChainState::Live {
+                        chain.state = ChainState::Returned;
+                        chain.ret = Some(ret.clone());
+                    }
+                }
+                chains
+            }
+            ExprKind::Assign(lhs, rhs, _) | ExprKind::AssignOp(_, lhs, rhs) => {
+                let chains = self.visit_expr(rhs, chains);
+                self.visit_expr(lhs, chains)
+            }
+            ExprKind::Call(func, args) => {
+                let chains = self.visit_expr(func, chains);
+                self.visit_exprs(args, chains)
+            }
+            ExprKind::MethodCall(_, receiver, args, _) => {
+                let chains = self.visit_expr(receiver, chains);
+                self.visit_exprs(args, chains)
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs, _) => {
+                let chains = self.visit_expr(lhs, chains);
+                self.visit_expr(rhs, chains)
+            }
+            ExprKind::Array(exprs) | ExprKind::Tup(exprs) => self.visit_exprs(exprs, chains),
+            ExprKind::Struct(_, fields, base) => {
+                let mut chains = chains;
+                for field in fields.iter() {
+                    chains = self.visit_expr(field.expr, chains);
+                }
+                match base {
+                    Some(base) => self.visit_expr(base, chains),
+                    None => chains,
+                }
+            }
+            ExprKind::Unary(_, inner)
+            | ExprKind::Field(inner, _)
+            | ExprKind::Cast(inner, _)
+            | ExprKind::Type(inner, _)
+            | ExprKind::AddrOf(_, _, inner)
+            | ExprKind::Repeat(inner, _)
+            | ExprKind::Become(inner)
+            | ExprKind::Yield(inner, _) => self.visit_expr(inner, chains),
+            ExprKind::Let(let_expr) => self.visit_expr(let_expr.init, chains),
+            ExprKind::Closure(closure) => self.visit_closure(closure, chains),
+            ExprKind::ConstBlock(_)
+            | ExprKind::Lit(_)
+            | ExprKind::Path(_)
+            | ExprKind::InlineAsm(_)
+            | ExprKind::OffsetOf(_, _)
+            | ExprKind::Err(_) => chains,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::testing::{get_fn_def_id, with_tcx};
+
+    fn get_chains(name: &str, code: &str, fn_name: &str) -> Vec<CondChain> {
+        with_tcx(name, code, |tcx| {
+            let body = tcx.hir().body_owned_by(get_fn_def_id(tcx, fn_name));
+            BranchVisitor::new(tcx, fn_name.to_string()).get_cond_chains(body)
+        })
+    }
+
+    #[test]
+    fn each_branch_gets_its_own_chain() {
+        let code = "pub fn sign(x: i32) -> i32 {\n    if x > 0 {\n        return 1;\n    }\n    match x {\n        0 => 0,\n        _ => -1,\n    }\n}\n";
+        let chains = get_chains("branches", code, "sign");
+        let conds: Vec<Vec<(String, String)>> = chains
+            .iter()
+            .map(|chain| {
+                chain
+                    .get_conds()
+                    .iter()
+                    .map(|c| (c.cond.clone(), c.value.clone()))
+                    .collect()
+            })
+            .collect();
+        // the returned chain is not split by the match
+        assert_eq!(chains.len(), 3);
+        assert_eq!(conds[0], vec![("x > 0".to_string(), "true".to_string())]);
+        assert_eq!(conds[1].len(), 2);
+        assert_eq!(conds[1][0], ("x > 0".to_string(), "false".to_string()));
+        assert_eq!(conds[1][1].0, "x");
+        assert_eq!(chains[0].get_path(), &vec![3]);
+    }
+
+    #[test]
+    fn chains_are_capped() {
+        let mut code = "pub fn many(x: u32) -> u32 {\n    let mut y = 0;\n".to_string();
+        for bit in 0..9 {
+            code.push_str(&format!(
+                "    if x & {} != 0 {{\n        y += 1;\n    }}\n",
+                1 << bit
+            ));
+        }
+        code.push_str("    y\n}\n");
+        let chains = get_chains("cap", &code, "many");
+        assert_eq!(chains.len(), MAX_CHAINS);
+    }
+}
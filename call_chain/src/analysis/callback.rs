@@ -1,21 +1,45 @@
 use rustc_driver::Compilation;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_hir::Safety;
 use rustc_interface::interface;
 use rustc_interface::Queries;
+use rustc_middle::lint::in_external_macro;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::tcx::PlaceTy;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::AssertKind;
+use rustc_middle::mir::BasicBlock;
+use rustc_middle::mir::BasicBlockData;
+use rustc_middle::mir::BinOp;
+use rustc_middle::mir::Const;
+use rustc_middle::mir::ConstOperand;
+use rustc_middle::mir::LocalDecl;
+use rustc_middle::mir::Location;
 use rustc_middle::mir::Operand;
+use rustc_middle::mir::Place;
+use rustc_middle::mir::ProjectionElem;
 use rustc_middle::mir::TerminatorKind;
 use rustc_middle::ty::GenericArgKind;
+use rustc_middle::ty::Instance;
+use rustc_middle::ty::ParamEnv;
 use rustc_middle::ty::Ty;
 use rustc_middle::ty::TyCtxt;
 use rustc_middle::ty::TyKind;
-use std::collections::HashSet;
+use rustc_middle::ty::TypeVisitableExt;
+use rustc_span::hygiene::{ExpnKind, MacroKind};
+use rustc_span::symbol::sym;
+use std::collections::{BTreeSet, HashSet};
 use std::fs::create_dir_all;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
-use super::exporter::CallsAndTypes;
+use super::exporter::{CallsAndTypes, ImplCall, MacroDef, PanicSite, UnsafeCall, UnsafeInfo};
 use super::hirvisitor::HirVisitor;
 use super::hirvisitor::VisitorData;
+use super::sourceinfo::SourceInfo;
+use crate::utils::get_output_file_name;
 
 pub struct MirCheckerCallbacks {
     pub source_name: String,
@@ -119,6 +143,327 @@ fn collect_subtypes<'tcx>(ty: Ty<'tcx>, tcx: TyCtxt<'tcx>, result: &mut HashSet<
     }
 }
 
+fn get_overflow_message(op: &BinOp) -> String {
+    let verb = match op {
+        BinOp::Add | BinOp::AddUnchecked | BinOp::AddWithOverflow => "add",
+        BinOp::Sub | BinOp::SubUnchecked | BinOp::SubWithOverflow => "subtract",
+        BinOp::Mul | BinOp::MulUnchecked | BinOp::MulWithOverflow => "multiply",
+        BinOp::Div => "divide",
+        BinOp::Rem => "calculate the remainder",
+        BinOp::Shl | BinOp::ShlUnchecked => "shift left",
+        BinOp::Shr | BinOp::ShrUnchecked => "shift right",
+        _ => return format!("attempt to compute `{:?}` with overflow", op),
+    };
+    format!("attempt to {} with overflow", verb)
+}
+
+fn get_option_or_result_name(tcx: TyCtxt<'_>, def_id: DefId) -> Option<&'static str> {
+    let impl_id = tcx.impl_of_method(def_id)?;
+    if let TyKind::Adt(adt, _) = tcx.type_of(impl_id).instantiate_identity().kind() {
+        if tcx.is_diagnostic_item(sym::Option, adt.did()) {
+            return Some("Option");
+        }
+        if tcx.is_diagnostic_item(sym::Result, adt.did()) {
+            return Some("Result");
+        }
+    }
+    None
+}
+
+// the panic machinery is made of lang items like `panic` and `panic_fmt`, the helpers
+// `assert_eq!` and `panic!()` call have no item but track the caller and never return
+fn is_panic_fn(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    if let Some(lang_item) = tcx.lang_items().from_def_id(def_id) {
+        let name = lang_item.name();
+        if name.as_str().starts_with("panic")
+            || name == sym::begin_panic
+            || name == sym::const_panic_fmt
+        {
+            return true;
+        }
+    }
+    tcx.codegen_fn_attrs(def_id)
+        .flags
+        .contains(CodegenFnAttrFlags::TRACK_CALLER)
+        && tcx
+            .fn_sig(def_id)
+            .skip_binder()
+            .output()
+            .skip_binder()
+            .is_never()
+}
+
+// collects the places where a function may panic: assertions inserted by the compiler
+// (bounds, overflow, division), calls into the panic machinery, `unwrap`/`expect` and
+// calls of `Index::index`
+fn collect_panic_sites<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    basic_block: &BasicBlockData<'tcx>,
+    panic_sites: &mut Vec<PanicSite>,
+) {
+    if basic_block.is_cleanup {
+        return;
+    }
+    let terminator = basic_block.terminator();
+    let span = terminator.source_info.span.source_callsite();
+    let source_map = tcx.sess.source_map();
+    let code = source_map.span_to_snippet(span).unwrap_or_default();
+    let loc = SourceInfo::from_span(span, source_map);
+    let panic_site = match &terminator.kind {
+        TerminatorKind::Assert { msg, .. } => {
+            let message = match &**msg {
+                AssertKind::BoundsCheck { .. } => "index out of bounds".to_string(),
+                AssertKind::Overflow(op, _, _) => get_overflow_message(op),
+                AssertKind::OverflowNeg(_) => "attempt to negate with overflow".to_string(),
+                AssertKind::DivisionByZero(_) => "attempt to divide by zero".to_string(),
+                AssertKind::RemainderByZero(_) => {
+                    "attempt to calculate the remainder with a divisor of zero".to_string()
+                }
+                AssertKind::MisalignedPointerDereference { .. } => {
+                    "misaligned pointer dereference".to_string()
+                }
+                AssertKind::ResumedAfterReturn(_) | AssertKind::ResumedAfterPanic(_) => {
+                    return;
+                }
+            };
+            PanicSite::new("assert", message, code, loc)
+        }
+        TerminatorKind::Call { func, .. } => {
+            let (def_id, _) = match func.const_fn_def() {
+                Some(fn_def) => fn_def,
+                None => return,
+            };
+            let def_path = format!(
+                "{}{}",
+                tcx.crate_name(def_id.krate),
+                tcx.def_path(def_id).to_string_no_crate_verbose()
+            );
+            let item_name = tcx.item_name(def_id).to_string();
+            let trait_id = tcx.trait_of_item(def_id);
+
+            if is_panic_fn(tcx, def_id) {
+                PanicSite::new("panic", format!("call of `{}`", def_path), code, loc)
+            } else if let Some(ty_name) = get_option_or_result_name(tcx, def_id) {
+                match item_name.as_str() {
+                    "unwrap" | "expect" | "unwrap_err" | "expect_err" => {
+                        let message = format!("`{}::{}`", ty_name, item_name);
+                        PanicSite::new("unwrap", message, code, loc)
+                    }
+                    _ => return,
+                }
+            } else if trait_id.is_some()
+                && (trait_id == tcx.lang_items().index_trait()
+                    || trait_id == tcx.lang_items().index_mut_trait())
+            {
+                PanicSite::new("index", format!("call of `{}`", def_path), code, loc)
+            } else {
+                return;
+            }
+        }
+        _ => return,
+    };
+    if !panic_sites.contains(&panic_site) {
+        panic_sites.push(panic_site);
+    }
+}
+
+struct RawPtrDerefVisitor<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    local_decls: &'a Vec<LocalDecl<'tcx>>,
+    derefs_raw_ptr: bool,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for RawPtrDerefVisitor<'a, 'tcx> {
+    fn visit_place(&mut self, place: &Place<'tcx>, _context: PlaceContext, _location: Location) {
+        let mut place_ty = PlaceTy::from_ty(self.local_decls[place.local.as_usize()].ty);
+        for elem in place.projection.iter() {
+            if elem == ProjectionElem::Deref && place_ty.ty.is_unsafe_ptr() {
+                self.derefs_raw_ptr = true;
+            }
+            place_ty = place_ty.projection_ty(self.tcx, elem);
+        }
+    }
+}
+
+// statics read or written by the fn are operands that point to the static,
+// const items are operands that are not evaluated yet
+struct GlobalVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    statics: BTreeSet<String>,
+    consts: BTreeSet<String>,
+}
+
+impl<'tcx> Visitor<'tcx> for GlobalVisitor<'tcx> {
+    fn visit_const_operand(&mut self, constant: &ConstOperand<'tcx>, _location: Location) {
+        if let Some(def_id) = constant.check_static_ptr(self.tcx) {
+            self.statics.insert(self.tcx.def_path_str(def_id));
+        }
+        if let Const::Unevaluated(unevaluated, _) = constant.const_ {
+            if self.tcx.def_kind(unevaluated.def) == DefKind::Const {
+                self.consts.insert(self.tcx.def_path_str(unevaluated.def));
+            }
+        }
+    }
+}
+
+// items of the crate expanded from a `name!(...)` invocation, with the place of the outermost
+// invocation, derives and attribute macros are left out as their items are written in the crate
+fn get_macro_defs(tcx: TyCtxt<'_>) -> Vec<MacroDef> {
+    let mut macro_defs: Vec<MacroDef> = Vec::new();
+    for def_id in tcx.hir_crate_items(()).definitions() {
+        let kind = match tcx.def_kind(def_id) {
+            DefKind::Struct => "struct",
+            DefKind::Enum => "enum",
+            DefKind::Union => "union",
+            DefKind::Trait => "trait",
+            DefKind::TyAlias => "type",
+            DefKind::Fn => "fn",
+            DefKind::Const => "const",
+            DefKind::Static { .. } => "static",
+            _ => continue,
+        };
+        let span = tcx.def_span(def_id);
+        if !span.from_expansion() {
+            continue;
+        }
+        let mut expn_data = span.ctxt().outer_expn_data();
+        while expn_data.call_site.from_expansion() {
+            expn_data = expn_data.call_site.ctxt().outer_expn_data();
+        }
+        let macro_name = match expn_data.kind {
+            ExpnKind::Macro(MacroKind::Bang, name) => name.to_string(),
+            _ => continue,
+        };
+        let def_id = def_id.to_def_id();
+        macro_defs.push(MacroDef {
+            def: format!(
+                "{}{}",
+                tcx.crate_name(def_id.krate),
+                tcx.def_path(def_id).to_string_no_crate_verbose()
+            ),
+            kind: kind.to_string(),
+            macro_name,
+            call_site: SourceInfo::from_span(expn_data.call_site, tcx.sess.source_map()),
+        });
+    }
+    macro_defs
+}
+
+fn export_macro_defs(tcx: TyCtxt<'_>) {
+    let directory_path = "./rfocxt/macro_items";
+    for macro_def in get_macro_defs(tcx) {
+        create_dir_all(directory_path).unwrap();
+        let file_name = get_output_file_name(&macro_def.def);
+        let file_path = PathBuf::from(&directory_path).join(format!("{}.json", file_name));
+        let mut file = File::create(&file_path).unwrap();
+        file.write_all(serde_json::to_string(&macro_def).unwrap().as_bytes())
+            .unwrap();
+    }
+}
+
+// calls with concrete generic args are resolved to the impl that is called,
+// the self type is taken from the impl, or from the args of a trait method
+fn get_impl_call<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    func: &Operand<'tcx>,
+    call_string: &str,
+) -> Option<ImplCall> {
+    let (def_id, args) = func.const_fn_def()?;
+    let (callee_id, callee_args) = if args.has_param() {
+        (def_id, args)
+    } else {
+        match Instance::try_resolve(tcx, ParamEnv::reveal_all(), def_id, args) {
+            Ok(Some(instance)) => (instance.def_id(), instance.args),
+            _ => (def_id, args),
+        }
+    };
+    let self_type = if let Some(impl_id) = tcx.impl_of_method(callee_id) {
+        tcx.type_of(impl_id).instantiate(tcx, callee_args)
+    } else if tcx.trait_of_item(callee_id).is_some() {
+        callee_args.type_at(0)
+    } else {
+        return None;
+    };
+    let mut generic_args: Vec<String> = Vec::new();
+    for arg in args.iter() {
+        generic_args.push(arg.to_string());
+    }
+    Some(ImplCall {
+        call: call_string.to_string(),
+        callee: format!(
+            "{}{}",
+            tcx.crate_name(callee_id.krate),
+            tcx.def_path(callee_id).to_string_no_crate_verbose()
+        ),
+        self_type: self_type.to_string(),
+        generic_args,
+    })
+}
+
+// the `# Safety` section of the doc comments of an unsafe function
+fn get_safety_doc(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    let mut doc_lines: Vec<String> = Vec::new();
+    for attr in tcx.get_attrs_unchecked(def_id).iter() {
+        if let Some(doc) = attr.doc_str() {
+            for line in doc.as_str().split('\n') {
+                doc_lines.push(line.strip_prefix(' ').unwrap_or(line).to_string());
+            }
+        }
+    }
+    let mut safety_lines: Vec<String> = Vec::new();
+    let mut in_safety = false;
+    let mut in_code = false;
+    for line in doc_lines.iter() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+        }
+        if !in_code && trimmed.starts_with('#') && trimmed.trim_start_matches('#').starts_with(' ')
+        {
+            if in_safety {
+                break;
+            }
+            in_safety = trimmed.trim_start_matches('#').trim().eq("Safety");
+        }
+        if in_safety {
+            safety_lines.push(line.clone());
+        }
+    }
+    safety_lines.join("\n").trim().to_string()
+}
+
+fn collect_unsafe_calls<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    basic_block: &BasicBlockData<'tcx>,
+    unsafe_calls: &mut Vec<UnsafeCall>,
+) {
+    let terminator = basic_block.terminator();
+    if let TerminatorKind::Call { func, .. } = &terminator.kind {
+        let span = terminator.source_info.span;
+        // unsafe calls generated by macros of other crates, like `format_args!`, are not counted
+        if basic_block.is_cleanup || in_external_macro(tcx.sess, span) {
+            return;
+        }
+        if let Some((def_id, _)) = func.const_fn_def() {
+            if tcx.fn_sig(def_id).skip_binder().safety() != Safety::Unsafe {
+                return;
+            }
+            let unsafe_call = UnsafeCall {
+                callee: format!(
+                    "{}{}",
+                    tcx.crate_name(def_id.krate),
+                    tcx.def_path(def_id).to_string_no_crate_verbose()
+                ),
+                safety_doc: get_safety_doc(tcx, def_id),
+                loc: SourceInfo::from_span(span.source_callsite(), tcx.sess.source_map()),
+            };
+            if !unsafe_calls.contains(&unsafe_call) {
+                unsafe_calls.push(unsafe_call);
+            }
+        }
+    }
+}
+
 impl MirCheckerCallbacks {
     fn run_analysis<'tcx, 'compiler>(&mut self, tcx: TyCtxt<'tcx>) {
         // let hir_krate = tcx.hir();
@@ -147,6 +492,7 @@ impl MirCheckerCallbacks {
         //         }
         //     }
         // }
+        export_macro_defs(tcx);
         let hir_map = tcx.hir();
         let mut visitor = HirVisitor::new(tcx, hir_map);
         // hir_map.visit_all_item_likes_in_crate(&mut visitor);
@@ -160,15 +506,39 @@ impl MirCheckerCallbacks {
                 has_ret,
                 mod_info,
                 visible,
+                reachable,
+                is_test,
+                test_source,
                 fn_source,
+                br_data,
+                is_unsafe_fn,
+                unsafe_blocks,
                 basic_blocks,
                 local_decls,
             } = data;
             // println!("{}", mod_info.name);
-            let mut calls: HashSet<String> = HashSet::new();
+            let mut calls: BTreeSet<String> = BTreeSet::new();
             let mut tys: HashSet<Ty<'tcx>> = HashSet::new();
-            let mut types: HashSet<String> = HashSet::new();
-            for basic_block in basic_blocks.iter() {
+            let mut types: BTreeSet<String> = BTreeSet::new();
+            let mut test_calls: BTreeSet<String> = BTreeSet::new();
+            let mut impl_calls: BTreeSet<ImplCall> = BTreeSet::new();
+            let mut panic_sites: Vec<PanicSite> = Vec::new();
+            let mut unsafe_calls: Vec<UnsafeCall> = Vec::new();
+            let mut raw_ptr_visitor = RawPtrDerefVisitor {
+                tcx,
+                local_decls: &local_decls,
+                derefs_raw_ptr: false,
+            };
+            let mut global_visitor = GlobalVisitor {
+                tcx,
+                statics: BTreeSet::new(),
+                consts: BTreeSet::new(),
+            };
+            for (index, basic_block) in basic_blocks.iter().enumerate() {
+                collect_panic_sites(tcx, basic_block, &mut panic_sites);
+                collect_unsafe_calls(tcx, basic_block, &mut unsafe_calls);
+                raw_ptr_visitor.visit_basic_block_data(BasicBlock::from_usize(index), basic_block);
+                global_visitor.visit_basic_block_data(BasicBlock::from_usize(index), basic_block);
                 if let TerminatorKind::Call {
                     func,
                     args,
@@ -183,9 +553,50 @@ impl MirCheckerCallbacks {
                     let kind_string = format!("{:#?}", &basic_block.terminator().kind);
                     let kind_strings: Vec<&str> = kind_string.splitn(3, ' ').into_iter().collect();
                     let kind_string = kind_strings[2];
-                    let call_string = &kind_string[..kind_string.find("(").unwrap()];
+                    // the arguments start at the first `(` outside of `<...>`,
+                    // as self types like `<(f64, f64) as Shape>` have parentheses
+                    let mut depth = 0;
+                    let mut last_char = ' ';
+                    let mut args_start = kind_string.find("(").unwrap();
+                    for (index, c) in kind_string.char_indices() {
+                        match c {
+                            '<' => depth += 1,
+                            '>' if last_char != '-' => depth -= 1,
+                            '(' if depth == 0 => {
+                                args_start = index;
+                                break;
+                            }
+                            _ => {}
+                        }
+                        last_char = c;
+                    }
+                    let call_string = &kind_string[..args_start];
                     // println!("提取的函数调用：{}", call_string);
                     calls.insert(call_string.to_string());
+                    if let Some(impl_call) = get_impl_call(tcx, func, call_string) {
+                        impl_calls.insert(impl_call);
+                    }
+
+                    // tests record the resolved callees by their def paths, which are
+                    // also the names of the callsandtypes files
+                    if is_test {
+                        if let Some((def_id, args)) = func.const_fn_def() {
+                            let def_id = match Instance::try_resolve(
+                                tcx,
+                                ParamEnv::reveal_all(),
+                                def_id,
+                                args,
+                            ) {
+                                Ok(Some(instance)) => instance.def_id(),
+                                _ => def_id,
+                            };
+                            test_calls.insert(format!(
+                                "{}{}",
+                                tcx.crate_name(def_id.krate),
+                                tcx.def_path(def_id).to_string_no_crate_verbose()
+                            ));
+                        }
+                    }
 
                     for arg in args.iter() {
                         if let Operand::Constant(constant) = &arg.node {
@@ -234,20 +645,153 @@ impl MirCheckerCallbacks {
             // for new_call in new_calls.iter() {
             //     calls.insert(new_call.clone());
             // }
-            let calls_and_types = CallsAndTypes::new(&mod_info.name, &calls, &types);
+            let mut calls_and_types =
+                CallsAndTypes::new(&mod_info.name, reachable, &fn_source, &calls, &types);
+            calls_and_types.doc = doc;
+            calls_and_types.is_test = is_test;
+            calls_and_types.test_source = test_source;
+            calls_and_types.test_calls = test_calls.into_iter().collect();
+            calls_and_types.unsafe_info = UnsafeInfo {
+                is_unsafe_fn,
+                unsafe_blocks,
+                derefs_raw_ptr: raw_ptr_visitor.derefs_raw_ptr,
+                unsafe_calls,
+            };
+            calls_and_types.impl_calls = impl_calls.into_iter().collect();
+            calls_and_types.statics = global_visitor.statics.into_iter().collect();
+            calls_and_types.consts = global_visitor.consts.into_iter().collect();
+            let file_name = get_output_file_name(&fn_name);
             let directory_path = "./rfocxt/callsandtypes";
             create_dir_all(&directory_path).unwrap();
-            let file_path = PathBuf::from(&directory_path).join(format!("{}.json", fn_name));
+            let file_path = PathBuf::from(&directory_path).join(format!("{}.json", file_name));
             let mut file = File::create(&file_path).unwrap();
             file.write_all(serde_json::to_string(&calls_and_types).unwrap().as_bytes())
                 .unwrap();
 
+            let directory_path = "./rfocxt/branches";
+            create_dir_all(directory_path).unwrap();
+            let file_path = PathBuf::from(&directory_path).join(format!("{}.json", file_name));
+            let mut file = File::create(&file_path).unwrap();
+            file.write_all(serde_json::to_string(&br_data).unwrap().as_bytes())
+                .unwrap();
+
+            let directory_path = "./rfocxt/panics";
+            create_dir_all(directory_path).unwrap();
+            let file_path = PathBuf::from(&directory_path).join(format!("{}.json", file_name));
+            let mut file = File::create(&file_path).unwrap();
+            file.write_all(serde_json::to_string(&panic_sites).unwrap().as_bytes())
+                .unwrap();
+
             let directory_path = "./rfocxt/basic_blocks";
             create_dir_all(&directory_path).unwrap();
-            let file_path = PathBuf::from(&directory_path).join(format!("{}.txt", fn_name));
+            let file_path = PathBuf::from(&directory_path).join(format!("{}.txt", file_name));
             let mut file = File::create(&file_path).unwrap();
             file.write_all(format!("{:#?}\n{:#?}", basic_blocks, local_decls).as_bytes())
                 .unwrap();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::testing::{get_fn_def_id, with_tcx};
+
+    #[test]
+    fn unsafe_calls_and_raw_pointer_derefs_are_found() {
+        let code = r#"
+/// Reads the value.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads.
+///
+/// # Examples
+///
+/// Nothing here.
+pub unsafe fn read(ptr: *const u8) -> u8 {
+    *ptr
+}
+
+pub fn first(bytes: &[u8]) -> u8 {
+    unsafe { read(bytes.as_ptr()) }
+}
+"#;
+        let (read_info, first_info) = with_tcx("unsafe_calls", code, |tcx| {
+            let mut infos: Vec<(bool, Vec<UnsafeCall>)> = Vec::new();
+            for fn_name in ["read", "first"] {
+                let mir = tcx.mir_built(get_fn_def_id(tcx, fn_name)).borrow();
+                let mut raw_ptr_visitor = RawPtrDerefVisitor {
+                    tcx,
+                    local_decls: &mir.local_decls.raw,
+                    derefs_raw_ptr: false,
+                };
+                let mut unsafe_calls: Vec<UnsafeCall> = Vec::new();
+                for (index, basic_block) in mir.basic_blocks.iter().enumerate() {
+                    collect_unsafe_calls(tcx, basic_block, &mut unsafe_calls);
+                    raw_ptr_visitor
+                        .visit_basic_block_data(BasicBlock::from_usize(index), basic_block);
+                }
+                infos.push((raw_ptr_visitor.derefs_raw_ptr, unsafe_calls));
+            }
+            (infos.remove(0), infos.remove(0))
+        });
+        assert!(read_info.0);
+        assert!(read_info.1.is_empty());
+        assert!(!first_info.0);
+        assert_eq!(first_info.1.len(), 1);
+        assert_eq!(first_info.1[0].callee, "lib::read");
+        assert_eq!(
+            first_info.1[0].safety_doc,
+            "# Safety\n\n`ptr` must be valid for reads."
+        );
+    }
+
+    #[test]
+    fn items_expanded_from_bang_macros_are_found() {
+        let code = r#"
+macro_rules! make_struct {
+    ($name:ident) => {
+        pub struct $name;
+    };
+}
+
+macro_rules! make_getter {
+    ($name:ident) => {
+        make_struct!(Inner);
+        pub fn $name() -> u8 {
+            1
+        }
+    };
+}
+
+make_struct!(Meters);
+make_getter!(get);
+
+#[derive(Clone)]
+pub struct Written;
+"#;
+        let mut macro_defs = with_tcx("macro_defs", code, get_macro_defs);
+        macro_defs.sort_by(|a, b| a.def.cmp(&b.def));
+        let found: Vec<(&str, &str, &str, usize)> = macro_defs
+            .iter()
+            .map(|macro_def| {
+                (
+                    macro_def.def.as_str(),
+                    macro_def.kind.as_str(),
+                    macro_def.macro_name.as_str(),
+                    macro_def.call_site.get_startline(),
+                )
+            })
+            .collect();
+        // nested invocations point to the outermost one
+        assert_eq!(
+            found,
+            vec![
+                ("lib::Inner", "struct", "make_getter", 18),
+                ("lib::Meters", "struct", "make_struct", 17),
+                ("lib::get", "fn", "make_getter", 18),
+            ]
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use super::sourceinfo::SourceInfo;
+use crate::utils::get_assert_cond;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrData {
     name: String,
     mod_info: ModInfo,
@@ -39,6 +40,10 @@ impl BrData {
         }
     }
 
+    pub fn get_cond_chains(&self) -> &Vec<CondChain> {
+        &self.cond_chains
+    }
+
     pub fn chain_len(&self) -> usize {
         self.cond_chains.len()
     }
@@ -88,20 +93,20 @@ impl BrData {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SizeInfo {
     chain: usize,
     contra: usize,
     min_set: usize,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModInfo {
     pub name: String,
     pub loc: SourceInfo,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CondChain {
     id: usize,
     conds: Vec<Cond>,
@@ -123,6 +128,22 @@ impl CondChain {
         }
     }
 
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_conds(&self) -> &Vec<Cond> {
+        &self.conds
+    }
+
+    pub fn get_path(&self) -> &Vec<usize> {
+        &self.path
+    }
+
+    pub fn is_min_set(&self) -> bool {
+        self.min_set
+    }
+
     pub fn get_cond_set(&self) -> HashSet<(usize, String, String)> {
         self.conds
             .iter()
@@ -156,7 +177,7 @@ impl CondChain {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cond {
     pub cond: String,
     pub norm: Option<String>,
@@ -177,15 +198,109 @@ impl Cond {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanicSite {
+    pub kind: String,
+    pub message: String,
+    pub code: String,
+    // the condition checked by an `assert!`, `assert_eq!` or `assert_ne!` the site is in
+    #[serde(default)]
+    pub cond: Option<String>,
+    pub loc: SourceInfo,
+}
+
+impl PanicSite {
+    pub fn new(kind: &str, message: String, code: String, loc: SourceInfo) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message,
+            cond: get_assert_cond(&code),
+            code,
+            loc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsafeCall {
+    pub callee: String,
+    pub safety_doc: String,
+    pub loc: SourceInfo,
+}
+
+// a call of a method in an impl or a trait, with the self type and the generic args
+// the callee is instantiated with, e.g. `Foo<u8>` for `Foo::<u8>::get`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ImplCall {
+    pub call: String,
+    pub callee: String,
+    pub self_type: String,
+    pub generic_args: Vec<String>,
+}
+
+// an item expanded from a macro invocation in the crate, e.g. the static of `lazy_static!`,
+// the call site is the place of the outermost invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroDef {
+    pub def: String,
+    pub kind: String,
+    pub macro_name: String,
+    pub call_site: SourceInfo,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnsafeInfo {
+    pub is_unsafe_fn: bool,
+    pub unsafe_blocks: Vec<SourceInfo>,
+    pub derefs_raw_ptr: bool,
+    pub unsafe_calls: Vec<UnsafeCall>,
+}
+
+impl UnsafeInfo {
+    pub fn has_unsafe(&self) -> bool {
+        self.is_unsafe_fn
+            || !self.unsafe_blocks.is_empty()
+            || self.derefs_raw_ptr
+            || !self.unsafe_calls.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallsAndTypes {
     pub mod_name: String,
+    #[serde(default)]
+    pub reachable: bool,
+    #[serde(default)]
+    pub doc: String,
+    #[serde(default)]
+    pub is_test: bool,
+    #[serde(default)]
+    pub test_source: String,
+    #[serde(default)]
+    pub test_calls: Vec<String>,
+    #[serde(default)]
+    pub fn_source: Option<SourceInfo>,
+    #[serde(default)]
+    pub unsafe_info: UnsafeInfo,
+    #[serde(default)]
+    pub impl_calls: Vec<ImplCall>,
+    #[serde(default)]
+    pub statics: Vec<String>,
+    #[serde(default)]
+    pub consts: Vec<String>,
     pub calls: Vec<String>,
     pub types: Vec<String>,
 }
 
 impl CallsAndTypes {
-    pub fn new(mod_name: &String, calls: &HashSet<String>, types: &HashSet<String>) -> Self {
+    pub fn new(
+        mod_name: &str,
+        reachable: bool,
+        fn_source: &SourceInfo,
+        calls: &BTreeSet<String>,
+        types: &BTreeSet<String>,
+    ) -> Self {
+        // the sets keep calls and types sorted by name, so the output is the same between runs
         let mut calls_vec: Vec<String> = Vec::new();
         for call in calls.iter() {
             calls_vec.push(call.clone());
@@ -195,7 +310,17 @@ impl CallsAndTypes {
             types_vec.push(a_type.clone());
         }
         CallsAndTypes {
-            mod_name: mod_name.clone(),
+            mod_name: mod_name.to_string(),
+            reachable,
+            doc: String::new(),
+            is_test: false,
+            test_source: String::new(),
+            test_calls: Vec::new(),
+            fn_source: Some(fn_source.clone()),
+            unsafe_info: UnsafeInfo::default(),
+            impl_calls: Vec::new(),
+            statics: Vec::new(),
+            consts: Vec::new(),
             calls: calls_vec,
             types: types_vec,
         }
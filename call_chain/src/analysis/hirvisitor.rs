@@ -2,21 +2,46 @@ use rustc_ast::token::CommentKind;
 use rustc_ast::AttrKind;
 use rustc_hir::def_id::CRATE_DEF_ID;
 use rustc_hir::intravisit::{self, Visitor};
-use rustc_hir::{self, BodyId, FnDecl};
+use rustc_hir::{self, BlockCheckMode, BodyId, FnDecl, UnsafeSource};
 use rustc_middle::hir::map::Map;
 use rustc_middle::hir::nested_filter;
+use rustc_middle::lint::in_external_macro;
 use rustc_middle::mir::{BasicBlockData, LocalDecl};
 use rustc_middle::ty::TyCtxt;
 use rustc_span::symbol::sym;
 use syn::parse_str;
 
-use super::exporter::ModInfo;
+use super::branchvisitor::BranchVisitor;
+use super::exporter::{BrData, ModInfo};
 use super::sourceinfo::SourceInfo;
 
 fn is_valid_code(code: &str) -> bool {
     parse_str::<syn::Item>(code).is_ok()
 }
 
+struct UnsafeBlockVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    unsafe_blocks: Vec<SourceInfo>,
+}
+
+impl<'tcx> Visitor<'tcx> for UnsafeBlockVisitor<'tcx> {
+    type NestedFilter = nested_filter::OnlyBodies;
+
+    fn nested_visit_map(&mut self) -> Self::Map {
+        self.tcx.hir()
+    }
+
+    fn visit_block(&mut self, block: &'tcx rustc_hir::Block<'tcx>) -> Self::Result {
+        if let BlockCheckMode::UnsafeBlock(UnsafeSource::UserProvided) = block.rules {
+            self.unsafe_blocks.push(SourceInfo::from_span(
+                block.span,
+                self.tcx.sess.source_map(),
+            ));
+        }
+        intravisit::walk_block(self, block);
+    }
+}
+
 pub struct VisitorData<'tcx> {
     pub id: String,
     pub fn_name: String,
@@ -24,7 +49,13 @@ pub struct VisitorData<'tcx> {
     pub has_ret: bool,
     pub mod_info: ModInfo,
     pub visible: bool,
+    pub reachable: bool,
+    pub is_test: bool,
+    pub test_source: String,
     pub fn_source: SourceInfo,
+    pub br_data: BrData,
+    pub is_unsafe_fn: bool,
+    pub unsafe_blocks: Vec<SourceInfo>,
     pub basic_blocks: Vec<BasicBlockData<'tcx>>,
     pub local_decls: Vec<LocalDecl<'tcx>>,
 }
@@ -59,6 +90,55 @@ impl<'tcx> HirVisitor<'tcx> {
         visibility.is_accessible_from(CRATE_DEF_ID.to_def_id(), self.tcx)
             && !source.get_file().contains("main.rs")
     }
+
+    fn is_reachable_from_outside(
+        &self,
+        id: rustc_hir::def_id::LocalDefId,
+        source: &SourceInfo,
+    ) -> bool {
+        self.is_accessible_from_crate(id.to_def_id(), source)
+            && self.tcx.effective_visibilities(()).is_exported(id)
+    }
+
+    // `#[test]` functions are paired with a generated const of the same name
+    // carrying `#[rustc_test_marker]` in the same module.
+    fn is_test_fn(&self, id: rustc_hir::def_id::LocalDefId) -> bool {
+        let name = match self.tcx.opt_item_name(id.to_def_id()) {
+            Some(name) => name,
+            None => return false,
+        };
+        let parent = self.tcx.parent_module_from_def_id(id);
+        self.hir_map.module_items(parent).any(|item_id| {
+            let def_id = item_id.owner_id.def_id;
+            def_id != id
+                && self.tcx.opt_item_name(def_id.to_def_id()) == Some(name)
+                && self
+                    .hir_map
+                    .attrs(self.tcx.local_def_id_to_hir_id(def_id))
+                    .iter()
+                    .any(|attr| attr.has_name(sym::rustc_test_marker))
+        })
+    }
+
+    fn get_test_source(&self, id: rustc_hir::def_id::LocalDefId, span: rustc_span::Span) -> String {
+        let mut test_span = span;
+        let attrs = self.hir_map.attrs(self.tcx.local_def_id_to_hir_id(id));
+        for attr in attrs {
+            if !attr.span.from_expansion() && attr.span.lo() < test_span.lo() {
+                test_span = test_span.with_lo(attr.span.lo());
+            }
+        }
+        let test_source = SourceInfo::from_span(test_span, self.tcx.sess.source_map());
+        let indent = " ".repeat(test_source.get_startcolumn() - 1);
+        let mut result = String::from("#[test]\n");
+        for (i, line) in test_source.get_string().lines().enumerate() {
+            if i > 0 {
+                result.push('\n');
+            }
+            result.push_str(line.strip_prefix(&indent).unwrap_or(line));
+        }
+        result
+    }
 }
 
 impl<'tcx> Visitor<'tcx> for HirVisitor<'tcx> {
@@ -114,6 +194,19 @@ impl<'tcx> Visitor<'tcx> for HirVisitor<'tcx> {
                 warn!("Skip because it is automatically derived");
                 return;
             }
+            if attrs
+                .iter()
+                .any(|attr| attr.has_name(sym::rustc_test_marker))
+            {
+                warn!("Skip because it is generated by the test harness");
+                return;
+            }
+        }
+
+        // Skip functions expanded from macros of other crates, their source is not in the crate
+        if in_external_macro(self.tcx.sess, span) {
+            warn!("Skip because it is expanded from an external macro");
+            return;
         }
 
         // Skip functions that are not valid code
@@ -124,37 +217,35 @@ impl<'tcx> Visitor<'tcx> for HirVisitor<'tcx> {
             return;
         }
 
-        // write function source code to file
-        // let dir_path = format!("./rbrinfo/{}", id_str);
-        // let file_path = format!("{}/code.rs", dir_path);
-        // fs::create_dir_all(dir_path).unwrap();
-        // let mut file = File::create(file_path).unwrap();
-        // file.write_all(code.as_bytes()).unwrap();
-
         let hir = self.hir_map.body(b);
         let mir = self.tcx.mir_built(id).borrow();
 
-        // write HIR to file
-        // let dir_path = format!("./rbrinfo/{}", id_str);
-        // let file_path = format!("{}/hir.txt", dir_path);
-        // fs::create_dir_all(dir_path).unwrap();
-        // let mut file = File::create(file_path).unwrap();
-        // let buf = format!("{:#?}", hir);
-        // file.write_all(buf.as_bytes()).unwrap();
-
-        // tranverse HIR
-        // let mut visitor = BranchVisitor::new(
-        //     self.tcx,
-        //     id_str.clone(),
-        //     fn_name.clone(),
-        //     fn_source.clone(),
-        //     self.tcx.typeck(hir.id().hir_id.owner),
-        // );
-        // intravisit::walk_body::<BranchVisitor>(&mut visitor, &hir);
-        // visitor.output_map();
+        // collect the condition chains of the branches
+        let mut visitor = BranchVisitor::new(self.tcx, fn_name.clone());
+        let cond_chains = visitor.get_cond_chains(hir);
+
+        // find unsafe blocks written in the function
+        let is_unsafe_fn = matches!(
+            _fk.header(),
+            Some(header) if header.safety == rustc_hir::Safety::Unsafe
+        );
+        let mut unsafe_visitor = UnsafeBlockVisitor {
+            tcx: self.tcx,
+            unsafe_blocks: Vec::new(),
+        };
+        unsafe_visitor.visit_body(hir);
 
         // check visibility
         let visible = self.is_accessible_from_crate(def_id, &fn_source);
+        let reachable = self.is_reachable_from_outside(id, &fn_source);
+
+        // keep the source of test functions so they can be shown as examples
+        let is_test = self.is_test_fn(id);
+        let test_source = if is_test {
+            self.get_test_source(id, span)
+        } else {
+            String::new()
+        };
 
         // get doc comments
         let hir_id = self.tcx.local_def_id_to_hir_id(id);
@@ -173,6 +264,20 @@ impl<'tcx> Visitor<'tcx> for HirVisitor<'tcx> {
             }
         }
 
+        let mut br_data = BrData::new(
+            fn_name.clone(),
+            mod_info.clone(),
+            visible,
+            fn_source.clone(),
+            doc.clone(),
+            code.lines().map(|line| line.to_string()).collect(),
+        );
+        for cond_chain in cond_chains {
+            br_data.add_chain(cond_chain);
+        }
+        br_data.set_min_set();
+        br_data.set_size();
+
         let data = VisitorData {
             id: id_str,
             fn_name,
@@ -180,7 +285,13 @@ impl<'tcx> Visitor<'tcx> for HirVisitor<'tcx> {
             has_ret,
             mod_info: mod_info.clone(),
             visible,
+            reachable,
+            is_test,
+            test_source,
             fn_source,
+            br_data,
+            is_unsafe_fn,
+            unsafe_blocks: unsafe_visitor.unsafe_blocks,
             basic_blocks: mir.basic_blocks.raw.to_vec(),
             local_decls: mir.local_decls.raw.to_vec(),
         };
@@ -1,4 +1,6 @@
+#[cfg(feature = "rustc")]
 use rustc_span::source_map::SourceMap;
+#[cfg(feature = "rustc")]
 use rustc_span::FileName;
 use std::fmt::Debug;
 use std::fs::File;
@@ -14,6 +16,23 @@ pub struct SourceInfo {
 }
 
 impl SourceInfo {
+    pub fn new(
+        file_path: &str,
+        start_line: usize,
+        start_column: usize,
+        end_line: usize,
+        end_column: usize,
+    ) -> Self {
+        SourceInfo {
+            file_path: file_path.to_string(),
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+
+    #[cfg(feature = "rustc")]
     pub fn from_span(span: rustc_span::Span, source_map: &SourceMap) -> Self {
         let start = source_map.lookup_char_pos(span.lo());
         let end = source_map.lookup_char_pos(span.hi());
@@ -0,0 +1,70 @@
+// Runs rustc on a snippet for the unit tests of the analyses
+use rustc_driver::Compilation;
+use rustc_hir::def_id::LocalDefId;
+use rustc_interface::interface;
+use rustc_interface::Queries;
+use rustc_middle::ty::TyCtxt;
+use std::fs;
+
+use crate::utils::compile_time_sysroot;
+
+struct TestCallbacks<F, T> {
+    analysis: Option<F>,
+    result: Option<T>,
+}
+
+impl<F, T> rustc_driver::Callbacks for TestCallbacks<F, T>
+where
+    F: for<'tcx> FnOnce(TyCtxt<'tcx>) -> T + Send,
+    T: Send,
+{
+    // the analyses run after expansion like in MirCheckerCallbacks, so the built mir is not stolen
+    fn after_expansion<'tcx>(
+        &mut self,
+        _compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        let analysis = self.analysis.take().unwrap();
+        self.result = Some(queries.global_ctxt().unwrap().enter(analysis));
+        Compilation::Stop
+    }
+}
+
+// compiles the code as a lib named after the test and runs the analysis on it
+pub fn with_tcx<F, T>(name: &str, code: &str, analysis: F) -> T
+where
+    F: for<'tcx> FnOnce(TyCtxt<'tcx>) -> T + Send,
+    T: Send,
+{
+    let dir = std::env::temp_dir().join(format!("call_chain_{}", name));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("lib.rs");
+    fs::write(&file, code).unwrap();
+    let mut args = vec![
+        "rustc".to_string(),
+        file.to_string_lossy().to_string(),
+        "--crate-type=lib".to_string(),
+        "--edition=2021".to_string(),
+        "--out-dir".to_string(),
+        dir.to_string_lossy().to_string(),
+    ];
+    if let Some(sysroot) = compile_time_sysroot() {
+        args.push("--sysroot".to_string());
+        args.push(sysroot);
+    }
+    let mut callbacks = TestCallbacks {
+        analysis: Some(analysis),
+        result: None,
+    };
+    rustc_driver::RunCompiler::new(&args, &mut callbacks)
+        .run()
+        .unwrap();
+    callbacks.result.unwrap()
+}
+
+pub fn get_fn_def_id(tcx: TyCtxt<'_>, fn_name: &str) -> LocalDefId {
+    tcx.hir()
+        .body_owners()
+        .find(|def_id| tcx.item_name(def_id.to_def_id()).as_str() == fn_name)
+        .unwrap()
+}
@@ -59,6 +59,10 @@ fn main() {
 
             // Disable unwind to simplify the CFG
             rustc_args.push("-Cpanic=abort".to_owned());
+            if rustc_args.iter().any(|e| e == "--test") {
+                // Test harnesses refuse to build with panic=abort unless asked to
+                rustc_args.push("-Zpanic_abort_tests".to_owned());
+            }
 
             let mut callbacks = analysis::callback::MirCheckerCallbacks::new();
 
@@ -172,24 +172,43 @@ fn in_cargo_mir_checker() {
         // Now we run `cargo rustc $FLAGS $ARGS`, giving the user the
         // chance to add additional arguments. `FLAGS` is set to identify
         // this target.  The user gets to control what gets actually passed to mir-checker.
-        let mut cmd = cargo();
-        cmd.arg("check"); // using `check` may speed up the analysis than using `rustc`
+        // Libraries and binaries are checked twice: once as usual and once with
+        // the test profile so that their `#[test]` functions are analyzed as well.
+        let mut target_args: Vec<Vec<String>> = Vec::new();
+        let mut top_crate_name = current_crate.name.clone();
         match kind.as_str() {
             "bin" => {
-                cmd.arg("--bin").arg(target.name);
+                target_args.push(vec!["--bin".to_string(), target.name.clone()]);
+                target_args.push(vec![
+                    "--bin".to_string(),
+                    target.name.clone(),
+                    "--profile".to_string(),
+                    "test".to_string(),
+                ]);
             }
             "lib" => {
-                cmd.arg("--lib");
+                target_args.push(vec!["--lib".to_string()]);
+                target_args.push(vec![
+                    "--lib".to_string(),
+                    "--profile".to_string(),
+                    "test".to_string(),
+                ]);
+            }
+            "test" => {
+                // Integration tests are crates of their own
+                target_args.push(vec!["--test".to_string(), target.name.clone()]);
+                top_crate_name = target.name.clone();
             }
             _ => continue,
         }
 
         // Add cargo args until first `--`.
+        let mut cargo_args: Vec<String> = Vec::new();
         while let Some(arg) = args.next() {
             if arg == "--" {
                 break;
             }
-            cmd.arg(arg);
+            cargo_args.push(arg);
         }
 
         // Serialize the remaining args into a special environemt variable.
@@ -201,30 +220,37 @@ fn in_cargo_mir_checker() {
         // crate name that we want to analyze, by doing this we can dispatch
         // dependencies to the real `rustc` and top-level crate to `mir-checker`
         let args_vec: Vec<String> = args.collect();
-        cmd.env(
-            "MIR_CHECKER_ARGS",
-            serde_json::to_string(&args_vec).expect("failed to serialize args"),
-        );
-        cmd.env("MIR_CHECKER_TOP_CRATE_NAME", current_crate.name.clone());
-
-        // Replace the rustc executable through RUSTC_WRAPPER environment variable
-        let path = std::env::current_exe().expect("current executable path invalid");
-        cmd.env("RUSTC_WRAPPER", path);
-
-        if verbose {
-            cmd.env("MIR_CHECKER_VERBOSE", ""); // this makes `inside_cargo_rustc` verbose.
-            eprintln!("+ {:?}", cmd);
-        }
 
-        // Execute cmd
-        let exit_status = cmd
-            .spawn()
-            .expect("could not run cargo")
-            .wait()
-            .expect("failed to wait for cargo?");
+        for target_arg in target_args.iter() {
+            let mut cmd = cargo();
+            cmd.arg("check"); // using `check` may speed up the analysis than using `rustc`
+            cmd.args(target_arg);
+            cmd.args(&cargo_args);
+            cmd.env(
+                "MIR_CHECKER_ARGS",
+                serde_json::to_string(&args_vec).expect("failed to serialize args"),
+            );
+            cmd.env("MIR_CHECKER_TOP_CRATE_NAME", top_crate_name.clone());
+
+            // Replace the rustc executable through RUSTC_WRAPPER environment variable
+            let path = std::env::current_exe().expect("current executable path invalid");
+            cmd.env("RUSTC_WRAPPER", path);
+
+            if verbose {
+                cmd.env("MIR_CHECKER_VERBOSE", ""); // this makes `inside_cargo_rustc` verbose.
+                eprintln!("+ {:?}", cmd);
+            }
 
-        if !exit_status.success() {
-            std::process::exit(exit_status.code().unwrap_or(-1))
+            // Execute cmd
+            let exit_status = cmd
+                .spawn()
+                .expect("could not run cargo")
+                .wait()
+                .expect("failed to wait for cargo?");
+
+            if !exit_status.success() {
+                std::process::exit(exit_status.code().unwrap_or(-1))
+            }
         }
     }
 }
@@ -1,30 +1,51 @@
-#![feature(rustc_private)]
+#![cfg_attr(feature = "rustc", feature(rustc_private))]
 // #![feature(custom_mir)]
 
+#[cfg(feature = "rustc")]
 extern crate rustc_abi;
+#[cfg(feature = "rustc")]
 extern crate rustc_ast;
+#[cfg(feature = "rustc")]
 extern crate rustc_data_structures;
+#[cfg(feature = "rustc")]
 extern crate rustc_driver;
+#[cfg(feature = "rustc")]
 extern crate rustc_errors;
+#[cfg(feature = "rustc")]
 extern crate rustc_hir;
+#[cfg(feature = "rustc")]
 extern crate rustc_index;
+#[cfg(feature = "rustc")]
 extern crate rustc_interface;
+#[cfg(feature = "rustc")]
 extern crate rustc_middle;
+#[cfg(feature = "rustc")]
 extern crate rustc_session;
+#[cfg(feature = "rustc")]
 extern crate rustc_span;
+#[cfg(feature = "rustc")]
 extern crate rustc_target;
+#[cfg(feature = "rustc")]
 extern crate rustc_type_ir;
+#[cfg(feature = "rustc")]
 extern crate thin_vec;
+#[cfg(feature = "rustc")]
 #[macro_use]
 extern crate log;
 
 // Modules for static analyses
 pub mod analysis {
     // Definitions of callbacks for rustc
+    #[cfg(feature = "rustc")]
+    pub mod branchvisitor;
+    #[cfg(feature = "rustc")]
     pub mod callback;
     pub mod exporter;
+    #[cfg(feature = "rustc")]
     pub mod hirvisitor;
     pub mod sourceinfo;
+    #[cfg(all(test, feature = "rustc"))]
+    pub mod testing;
 }
 
 // Useful utilities
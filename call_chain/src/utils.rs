@@ -20,3 +20,137 @@ pub fn compile_time_sysroot() -> Option<String> {
             .to_owned(),
     })
 }
+
+const MAX_FILE_NAME_LEN: usize = 80;
+
+/// Returns the file name stem used for every output of a function.
+/// The def path is sanitized to ASCII alphanumerics, `_` and `.`, truncated, and followed
+/// by the low 32 bits of a FNV-1a hash of the whole def path. Two functions whose names
+/// sanitize and truncate to the same prefix share a file only if these hashes collide too,
+/// which is unlikely but not impossible.
+pub fn get_output_file_name(fn_name: &str) -> String {
+    let mut name = String::new();
+    for c in fn_name.replace("::", ".").chars() {
+        let c = if c.is_ascii_alphanumeric() || c == '.' {
+            c
+        } else {
+            '_'
+        };
+        if c == '_' && name.ends_with('_') {
+            continue;
+        }
+        name.push(c);
+    }
+    name.truncate(MAX_FILE_NAME_LEN);
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in fn_name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{}-{:08x}", name, hash as u32)
+}
+
+// the arguments of a macro invocation, split at the commas outside of nested delimiters
+// and string literals
+fn split_macro_args(args: &str) -> Vec<String> {
+    let mut split_args: Vec<String> = Vec::new();
+    let mut arg = String::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in args.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else {
+            match c {
+                '"' => in_string = true,
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    split_args.push(arg.trim().to_string());
+                    arg.clear();
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        arg.push(c);
+    }
+    if !arg.trim().is_empty() {
+        split_args.push(arg.trim().to_string());
+    }
+    split_args
+}
+
+/// Returns the condition an `assert!`, `assert_eq!` or `assert_ne!` invocation checks,
+/// read from its source text, e.g. `a == b` for `assert_eq!(a, b)`.
+/// The `debug_` variants are read the same way, any other code has no condition.
+pub fn get_assert_cond(code: &str) -> Option<String> {
+    let (path, args) = code.split_once('!')?;
+    let name = path.trim().rsplit("::").next()?;
+    let name = name.strip_prefix("debug_").unwrap_or(name);
+    let args = args.trim();
+    let args = match args.chars().next()? {
+        '(' => args.strip_prefix('(')?.strip_suffix(')')?,
+        '[' => args.strip_prefix('[')?.strip_suffix(']')?,
+        '{' => args.strip_prefix('{')?.strip_suffix('}')?,
+        _ => return None,
+    };
+    let args = split_macro_args(args);
+    match name {
+        "assert" if !args.is_empty() => Some(args[0].clone()),
+        "assert_eq" if args.len() >= 2 => Some(format!("{} == {}", args[0], args[1])),
+        "assert_ne" if args.len() >= 2 => Some(format!("{} != {}", args[0], args[1])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_file_names_are_sanitized_def_paths() {
+        let name = get_output_file_name("sample::shapes::{impl#2}::fmt");
+        let (stem, hash) = name.rsplit_once('-').unwrap();
+        assert_eq!(stem, "sample.shapes._impl_2_.fmt");
+        assert_eq!(hash.len(), 8);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(name, get_output_file_name("sample::shapes::{impl#2}::fmt"));
+    }
+
+    #[test]
+    fn truncated_names_are_told_apart_by_the_hash() {
+        let prefix = "sample::".to_string() + &"a".repeat(MAX_FILE_NAME_LEN);
+        let first = get_output_file_name(&(prefix.clone() + "::first"));
+        let second = get_output_file_name(&(prefix + "::second"));
+        assert_eq!(first.len(), MAX_FILE_NAME_LEN + 9);
+        assert_eq!(first[..MAX_FILE_NAME_LEN], second[..MAX_FILE_NAME_LEN]);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn assert_conds_are_read_from_the_macro_source() {
+        assert_eq!(
+            get_assert_cond("assert!(x > 0, \"x is {}, not > 0\", x)"),
+            Some("x > 0".to_string())
+        );
+        assert_eq!(
+            get_assert_cond("assert_eq!(\n    v.get(0, 1),\n    Some(\",\")\n)"),
+            Some("v.get(0, 1) == Some(\",\")".to_string())
+        );
+        assert_eq!(
+            get_assert_cond("std::debug_assert_ne![a[i], b]"),
+            Some("a[i] != b".to_string())
+        );
+        assert_eq!(get_assert_cond("v[i]"), None);
+        assert_eq!(get_assert_cond("panic!(\"no\")"), None);
+        assert_eq!(get_assert_cond("assert_eq!(a)"), None);
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    fs::{self, create_dir_all, read_to_string, File},
+    io::Write,
+    path::PathBuf,
+    process::{self, Command},
+};
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use toml::Value;
+
+const MAX_DIAGNOSTICS: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    function: String,
+    passed: bool,
+    diagnostics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    total: usize,
+    passed: usize,
+    results: Vec<CheckResult>,
+}
+
+// a workspace root without `[package]` has no crate to check the contexts against
+fn create_check_crate(crate_path: &PathBuf, check_path: &PathBuf) -> Result<(), String> {
+    let toml_content = read_to_string(crate_path.join("Cargo.toml")).unwrap();
+    let toml_value: Value = toml_content.parse().unwrap();
+    let package_name = match toml_value
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+    {
+        Some(package_name) => package_name.to_string(),
+        None => {
+            return Err(format!(
+                "{:?} has no [package] name, --check needs the manifest of a crate!",
+                crate_path.join("Cargo.toml")
+            ))
+        }
+    };
+    let has_lib =
+        toml_value.get("lib").is_some() || fs::exists(crate_path.join("src/lib.rs")).unwrap();
+
+    let mut manifest = String::new();
+    manifest += "[package]\n";
+    manifest += "name = \"rfocxt_check\"\n";
+    manifest += "version = \"0.1.0\"\n";
+    manifest += "edition = \"2021\"\n\n";
+    manifest += "[dependencies]\n";
+    if has_lib {
+        manifest += &format!(
+            "{} = {{ path = {:?} }}\n",
+            package_name,
+            crate_path.to_string_lossy()
+        );
+    }
+    manifest += "\n[workspace]\n";
+    create_dir_all(check_path.join("src")).unwrap();
+    let mut file = File::create(check_path.join("Cargo.toml")).unwrap();
+    file.write_all(manifest.as_bytes()).unwrap();
+    Ok(())
+}
+
+// the first errors of a `--message-format=json` stream, as rustc renders them
+fn get_diagnostics(stdout: &str) -> Vec<String> {
+    let mut diagnostics: Vec<String> = Vec::new();
+    for line in stdout.lines() {
+        let message: JsonValue = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        if message["reason"] != "compiler-message" {
+            continue;
+        }
+        if message["message"]["level"] != "error" {
+            continue;
+        }
+        let text = message["message"]["message"].as_str().unwrap_or("");
+        if text.starts_with("aborting due to") {
+            continue;
+        }
+        if diagnostics.len() < MAX_DIAGNOSTICS {
+            if let Some(rendered) = message["message"]["rendered"].as_str() {
+                diagnostics.push(rendered.to_string());
+            }
+        }
+    }
+    diagnostics
+}
+
+fn check_context(check_path: &PathBuf, context: &String) -> CheckResult {
+    // the sources a failed check leaves behind must not reach the next one
+    let _ = fs::remove_dir_all(check_path.join("src"));
+    create_dir_all(check_path.join("src")).unwrap();
+    let lib_content = String::from("#![allow(unused)]\n") + context;
+    let mut file = File::create(check_path.join("src/lib.rs")).unwrap();
+    file.write_all(lib_content.as_bytes()).unwrap();
+
+    let check_output = Command::new("cargo")
+        .args(["check", "--offline", "--message-format=json"])
+        .current_dir(check_path)
+        .output()
+        .expect("Failed to run cargo check");
+
+    let mut diagnostics = get_diagnostics(&String::from_utf8_lossy(&check_output.stdout));
+    if !check_output.status.success() && diagnostics.is_empty() {
+        diagnostics.push(String::from_utf8_lossy(&check_output.stderr).to_string());
+    }
+    CheckResult {
+        function: String::new(),
+        passed: check_output.status.success(),
+        diagnostics,
+    }
+}
+
+pub fn check_all_contexts(crate_path: &PathBuf) {
+    let output_path = crate_path.join("rfocxt");
+    let check_path = output_path.join("check");
+    if let Err(message) = create_check_crate(crate_path, &check_path) {
+        eprintln!("{}", message);
+        process::exit(1)
+    }
+
+    // the manifest maps every function with a context to its files
+    let mut contexts: Vec<(String, String)> = Vec::new();
+    if let Ok(contents) = read_to_string(output_path.join("manifest.json")) {
+        let manifest: JsonValue = serde_json::from_str(&contents).unwrap();
+        if let Some(manifest) = manifest.as_object() {
+            for (function_name, files) in manifest.iter() {
+                if let Some(context_file) = files["context"].as_str() {
+                    contexts.push((function_name.clone(), context_file.to_string()));
+                }
+            }
+        }
+    }
+    contexts.sort();
+
+    let mut results: Vec<CheckResult> = Vec::new();
+    for (function_name, context_file) in contexts.iter() {
+        let context_path = output_path.join(context_file);
+        let context = match read_to_string(&context_path) {
+            Ok(context) => context,
+            Err(_) => continue,
+        };
+        let mut result = check_context(&check_path, &context);
+        result.function = function_name.clone();
+        results.push(result);
+    }
+
+    let report = CheckReport {
+        total: results.len(),
+        passed: results.iter().filter(|result| result.passed).count(),
+        results,
+    };
+    let mut file = File::create(output_path.join("check_report.json")).unwrap();
+    file.write_all(serde_json::to_string_pretty(&report).unwrap().as_bytes())
+        .unwrap();
+    println!(
+        "{}/{} contexts passed the check.",
+        report.passed, report.total
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    fn get_crate_path(dir_name: &str, manifest: &str, files: &[&str]) -> PathBuf {
+        let crate_path = temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&crate_path);
+        create_dir_all(crate_path.join("src")).unwrap();
+        fs::write(crate_path.join("Cargo.toml"), manifest).unwrap();
+        for file_name in files.iter() {
+            fs::write(crate_path.join(file_name), "").unwrap();
+        }
+        crate_path
+    }
+
+    fn compiler_message(level: &str, message: &str) -> String {
+        serde_json::json!({
+            "reason": "compiler-message",
+            "message": {
+                "level": level,
+                "message": message,
+                "rendered": format!("{}: {}\n", level, message),
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn check_crates_depend_on_the_lib_of_the_crate() {
+        let crate_path = get_crate_path(
+            "rfocxt_check_lib",
+            "[package]\nname = \"sample\"\n",
+            &["src/lib.rs"],
+        );
+        let check_path = crate_path.join("rfocxt/check");
+        create_check_crate(&crate_path, &check_path).unwrap();
+        let manifest = read_to_string(check_path.join("Cargo.toml")).unwrap();
+        assert!(manifest.starts_with("[package]\nname = \"rfocxt_check\"\n"));
+        assert!(manifest.contains(&format!("sample = {{ path = {:?} }}\n", crate_path)));
+        assert!(manifest.ends_with("\n[workspace]\n"));
+    }
+
+    #[test]
+    fn check_crates_of_binaries_have_no_dependency() {
+        let crate_path = get_crate_path(
+            "rfocxt_check_bin",
+            "[package]\nname = \"sample\"\n",
+            &["src/main.rs"],
+        );
+        let check_path = crate_path.join("rfocxt/check");
+        create_check_crate(&crate_path, &check_path).unwrap();
+        let manifest = read_to_string(check_path.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("[dependencies]\n\n[workspace]\n"));
+    }
+
+    #[test]
+    fn workspace_roots_have_no_check_crate() {
+        let crate_path = get_crate_path(
+            "rfocxt_check_workspace",
+            "[workspace]\nmembers = [\"sample\"]\n",
+            &[],
+        );
+        let check_path = crate_path.join("rfocxt/check");
+        assert!(create_check_crate(&crate_path, &check_path).is_err());
+        assert!(!fs::exists(check_path.join("Cargo.toml")).unwrap());
+    }
+
+    #[test]
+    fn only_the_first_errors_are_kept() {
+        let mut lines: Vec<String> = vec![
+            "not json".to_string(),
+            serde_json::json!({ "reason": "compiler-artifact" }).to_string(),
+            compiler_message("warning", "unused variable: `a`"),
+        ];
+        for i in 0..MAX_DIAGNOSTICS + 1 {
+            lines.push(compiler_message(
+                "error",
+                &format!("cannot find value `v{}`", i),
+            ));
+        }
+        lines.push(compiler_message(
+            "error",
+            "aborting due to 4 previous errors",
+        ));
+        let diagnostics = get_diagnostics(&lines.join("\n"));
+        assert_eq!(
+            diagnostics,
+            (0..MAX_DIAGNOSTICS)
+                .map(|i| format!("error: cannot find value `v{}`\n", i))
+                .collect::<Vec<String>>()
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum KeepDocs {
+    None,
+    Focal,
+    Fns,
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum Render {
+    Pretty,
+    Verbatim,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContextConfig {
+    max_tests: usize,
+    keep_docs: KeepDocs,
+    include_impls: bool,
+    trait_default_methods: bool,
+    type_depth: usize,
+    only_uncovered: Option<f64>,
+    render: Render,
+    nest_modules: bool,
+    syntax_only: bool,
+}
+
+impl ContextConfig {
+    pub fn new() -> Self {
+        ContextConfig {
+            max_tests: 3,
+            keep_docs: KeepDocs::None,
+            include_impls: false,
+            trait_default_methods: false,
+            type_depth: 1,
+            only_uncovered: None,
+            render: Render::Pretty,
+            nest_modules: false,
+            syntax_only: false,
+        }
+    }
+
+    pub fn insert_max_tests(&mut self, max_tests: usize) {
+        self.max_tests = max_tests;
+    }
+
+    pub fn get_max_tests(&self) -> usize {
+        self.max_tests
+    }
+
+    pub fn insert_keep_docs(&mut self, keep_docs: KeepDocs) {
+        self.keep_docs = keep_docs;
+    }
+
+    pub fn get_keep_docs(&self) -> &KeepDocs {
+        &self.keep_docs
+    }
+
+    pub fn insert_include_impls(&mut self, include_impls: bool) {
+        self.include_impls = include_impls;
+    }
+
+    pub fn get_include_impls(&self) -> bool {
+        self.include_impls
+    }
+
+    pub fn insert_trait_default_methods(&mut self, trait_default_methods: bool) {
+        self.trait_default_methods = trait_default_methods;
+    }
+
+    pub fn get_trait_default_methods(&self) -> bool {
+        self.trait_default_methods
+    }
+
+    pub fn insert_type_depth(&mut self, type_depth: usize) {
+        self.type_depth = type_depth;
+    }
+
+    pub fn get_type_depth(&self) -> usize {
+        self.type_depth
+    }
+
+    pub fn insert_only_uncovered(&mut self, only_uncovered: Option<f64>) {
+        self.only_uncovered = only_uncovered;
+    }
+
+    pub fn get_only_uncovered(&self) -> Option<f64> {
+        self.only_uncovered
+    }
+
+    pub fn insert_render(&mut self, render: Render) {
+        self.render = render;
+    }
+
+    pub fn get_render(&self) -> &Render {
+        &self.render
+    }
+
+    pub fn insert_nest_modules(&mut self, nest_modules: bool) {
+        self.nest_modules = nest_modules;
+    }
+
+    pub fn get_nest_modules(&self) -> bool {
+        self.nest_modules
+    }
+
+    pub fn insert_syntax_only(&mut self, syntax_only: bool) {
+        self.syntax_only = syntax_only;
+    }
+
+    pub fn get_syntax_only(&self) -> bool {
+        self.syntax_only
+    }
+}
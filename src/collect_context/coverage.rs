@@ -0,0 +1,343 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, read_to_string},
+    path::Path,
+};
+
+use call_chain::analysis::{exporter::BrData, sourceinfo::SourceInfo};
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+pub struct FnCoverage {
+    covered_lines: Vec<usize>,
+    uncovered_lines: Vec<usize>,
+    uncovered_chains: Vec<String>,
+}
+
+impl FnCoverage {
+    pub fn get_percent(&self) -> f64 {
+        let total = self.covered_lines.len() + self.uncovered_lines.len();
+        if total == 0 {
+            return 100.0;
+        }
+        self.covered_lines.len() as f64 * 100.0 / total as f64
+    }
+
+    pub fn has_data(&self) -> bool {
+        !self.covered_lines.is_empty() || !self.uncovered_lines.is_empty()
+    }
+
+    pub fn to_comments(&self, complete_fn_name: &String) -> String {
+        let mut comments = format!(
+            "\n// Coverage of `{}`: {}/{} lines ({:.1}%)\n",
+            complete_fn_name,
+            self.covered_lines.len(),
+            self.covered_lines.len() + self.uncovered_lines.len(),
+            self.get_percent()
+        );
+        if !self.covered_lines.is_empty() {
+            comments += &format!("// Covered lines: {}\n", get_ranges(&self.covered_lines));
+        }
+        if !self.uncovered_lines.is_empty() {
+            comments += &format!(
+                "// Uncovered lines: {}\n",
+                get_ranges(&self.uncovered_lines)
+            );
+        }
+        if !self.uncovered_chains.is_empty() {
+            comments += "// Uncovered condition chains:\n";
+            for chain in self.uncovered_chains.iter() {
+                comments += &format!("//   {}\n", chain);
+            }
+        }
+        comments
+    }
+}
+
+fn get_ranges(lines: &[usize]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let start = lines[index];
+        let mut end = start;
+        while index + 1 < lines.len() && lines[index + 1] == end + 1 {
+            index += 1;
+            end = lines[index];
+        }
+        if start == end {
+            ranges.push(start.to_string());
+        } else {
+            ranges.push(format!("{}-{}", start, end));
+        }
+        index += 1;
+    }
+    ranges.join(", ")
+}
+
+// relative paths in a report are relative to the crate root, where cargo runs the tests
+fn normalize_path(crate_path: &Path, path: &str) -> String {
+    let path = crate_path.join(path);
+    fs::canonicalize(&path)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+fn parse_lcov(
+    contents: &str,
+    crate_path: &Path,
+    coverage: &mut BTreeMap<String, BTreeMap<usize, u64>>,
+) {
+    let mut current_file = String::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(file) = line.strip_prefix("SF:") {
+            current_file = normalize_path(crate_path, file);
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let mut parts = data.split(',');
+            let line_number = parts.next().and_then(|part| part.parse::<usize>().ok());
+            let count = parts.next().and_then(|part| part.parse::<u64>().ok());
+            if let (Some(line_number), Some(count)) = (line_number, count) {
+                let line_counts = coverage.entry(current_file.clone()).or_default();
+                let line_count = line_counts.entry(line_number).or_insert(0);
+                *line_count = (*line_count).max(count);
+            }
+        } else if line.eq("end_of_record") {
+            current_file = String::new();
+        }
+    }
+}
+
+// segments of `llvm-cov export` are [line, col, count, has_count, is_region_entry, is_gap_region],
+// a line is executable if a region starts on it or it is wrapped by a counted region
+fn parse_llvm_cov_json(
+    json: &Value,
+    crate_path: &Path,
+    coverage: &mut BTreeMap<String, BTreeMap<usize, u64>>,
+) {
+    let data = match json["data"].as_array() {
+        Some(data) => data,
+        None => return,
+    };
+    for export in data.iter() {
+        let files = match export["files"].as_array() {
+            Some(files) => files,
+            None => continue,
+        };
+        for file in files.iter() {
+            let file_name = match file["filename"].as_str() {
+                Some(file_name) => normalize_path(crate_path, file_name),
+                None => continue,
+            };
+            let segments = match file["segments"].as_array() {
+                Some(segments) => segments,
+                None => continue,
+            };
+            let mut line_segments: HashMap<usize, Vec<(u64, bool, bool)>> = HashMap::new();
+            let mut last_line = 0;
+            for segment in segments.iter() {
+                let line = segment[0].as_u64().unwrap_or(0) as usize;
+                let count = segment[2].as_u64().unwrap_or(0);
+                let has_count = segment[3].as_bool().unwrap_or(false);
+                let is_region_entry = segment[4].as_bool().unwrap_or(false);
+                let is_gap_region = segment[5].as_bool().unwrap_or(false);
+                let is_counted_entry = has_count && is_region_entry && !is_gap_region;
+                line_segments
+                    .entry(line)
+                    .or_default()
+                    .push((count, has_count, is_counted_entry));
+                last_line = last_line.max(line);
+            }
+            let line_counts = coverage.entry(file_name).or_default();
+            let mut wrapped: Option<u64> = None;
+            for line in 1..=last_line {
+                let mut count: Option<u64> = wrapped;
+                if let Some(segments) = line_segments.get(&line) {
+                    for (segment_count, _, is_counted_entry) in segments.iter() {
+                        if *is_counted_entry {
+                            count = Some(count.unwrap_or(0).max(*segment_count));
+                        }
+                    }
+                    // the last segment on the line wraps the following lines
+                    let (last_count, last_has_count, _) = *segments.last().unwrap();
+                    wrapped = if last_has_count {
+                        Some(last_count)
+                    } else {
+                        None
+                    };
+                }
+                if let Some(count) = count {
+                    let line_count = line_counts.entry(line).or_insert(0);
+                    *line_count = (*line_count).max(count);
+                }
+            }
+        }
+    }
+}
+
+pub fn get_coverage(
+    crate_path: &Path,
+    coverage_path: &Path,
+    coverage: &mut BTreeMap<String, BTreeMap<usize, u64>>,
+) -> Result<(), String> {
+    let contents = match read_to_string(coverage_path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            return Err(format!(
+                "Can not read the coverage report {:?}!",
+                coverage_path
+            ))
+        }
+    };
+    match serde_json::from_str::<Value>(&contents) {
+        Ok(json) => parse_llvm_cov_json(&json, crate_path, coverage),
+        Err(_) => parse_lcov(&contents, crate_path, coverage),
+    }
+    // without a file of the crate every fn would look uncovered
+    let crate_dir = normalize_path(crate_path, ".");
+    if !coverage
+        .keys()
+        .any(|file_name| Path::new(file_name).starts_with(&crate_dir))
+    {
+        eprintln!(
+            "Warning: no file of the coverage report {:?} is in the crate {:?}!",
+            coverage_path, crate_path
+        );
+    }
+    Ok(())
+}
+
+pub fn get_fn_coverage(
+    crate_path: &Path,
+    fn_source: &SourceInfo,
+    br_data: &Option<BrData>,
+    coverage: &BTreeMap<String, BTreeMap<usize, u64>>,
+) -> FnCoverage {
+    let mut fn_coverage = FnCoverage {
+        covered_lines: Vec::new(),
+        uncovered_lines: Vec::new(),
+        uncovered_chains: Vec::new(),
+    };
+    let file_name = normalize_path(crate_path, &fn_source.get_file());
+    let line_counts = match coverage.get(&file_name) {
+        Some(line_counts) => line_counts,
+        None => return fn_coverage,
+    };
+    for line in fn_source.get_startline()..=fn_source.get_endline() {
+        match line_counts.get(&line) {
+            Some(0) => fn_coverage.uncovered_lines.push(line),
+            Some(_) => fn_coverage.covered_lines.push(line),
+            None => {}
+        }
+    }
+    if let Some(br_data) = br_data {
+        for cond_chain in br_data.get_cond_chains().iter() {
+            let is_covered = cond_chain
+                .get_path()
+                .iter()
+                .all(|line| line_counts.get(line).map_or(true, |count| *count > 0));
+            if is_covered {
+                continue;
+            }
+            let mut conds: Vec<String> = Vec::new();
+            for cond in cond_chain.get_conds().iter() {
+                conds.push(format!("`{}` is `{}`", cond.cond, cond.value));
+            }
+            fn_coverage.uncovered_chains.push(format!(
+                "#{}: {}",
+                cond_chain.get_id(),
+                conds.join(", ")
+            ));
+        }
+    }
+    fn_coverage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lcov_paths_are_relative_to_the_crate() {
+        let contents = "TN:\nSF:src/lib.rs\nDA:1,0\nDA:2,3\nend_of_record\nSF:/other/main.rs\nDA:5,1\nend_of_record\n";
+        let mut coverage: BTreeMap<String, BTreeMap<usize, u64>> = BTreeMap::new();
+        parse_lcov(contents, Path::new("/no/such/crate"), &mut coverage);
+        assert_eq!(
+            coverage.keys().collect::<Vec<_>>(),
+            vec!["/no/such/crate/src/lib.rs", "/other/main.rs"]
+        );
+        let lib_counts = &coverage["/no/such/crate/src/lib.rs"];
+        assert_eq!(lib_counts.get(&1), Some(&0));
+        assert_eq!(lib_counts.get(&2), Some(&3));
+        assert_eq!(coverage["/other/main.rs"].get(&5), Some(&1));
+    }
+
+    #[test]
+    fn lcov_keeps_the_highest_count_of_a_line() {
+        let contents =
+            "SF:src/lib.rs\nDA:7,2\nend_of_record\nSF:src/lib.rs\nDA:7,0\nDA:8,x\nend_of_record\n";
+        let mut coverage: BTreeMap<String, BTreeMap<usize, u64>> = BTreeMap::new();
+        parse_lcov(contents, Path::new("/no/such/crate"), &mut coverage);
+        let line_counts = &coverage["/no/such/crate/src/lib.rs"];
+        assert_eq!(line_counts.get(&7), Some(&2));
+        assert_eq!(line_counts.get(&8), None);
+    }
+
+    #[test]
+    fn llvm_cov_regions_count_every_line_they_wrap() {
+        // `if` on line 3 opens a region that is never entered and ends on line 5
+        let contents = r#"{
+            "type": "llvm.coverage.json.export",
+            "version": "2.0.1",
+            "data": [{
+                "files": [{
+                    "filename": "src/lib.rs",
+                    "segments": [
+                        [1, 20, 5, true, true, false],
+                        [3, 10, 0, true, true, false],
+                        [5, 6, 5, true, false, false],
+                        [7, 2, 0, false, false, false]
+                    ]
+                }]
+            }]
+        }"#;
+        let json: Value = serde_json::from_str(contents).unwrap();
+        let mut coverage: BTreeMap<String, BTreeMap<usize, u64>> = BTreeMap::new();
+        parse_llvm_cov_json(&json, Path::new("/no/such/crate"), &mut coverage);
+        let line_counts = &coverage["/no/such/crate/src/lib.rs"];
+        let counts = (1..=8)
+            .map(|line| line_counts.get(&line).copied())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                Some(5),
+                Some(5),
+                Some(5),
+                Some(0),
+                Some(0),
+                Some(5),
+                Some(5),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn unreadable_reports_are_an_error() {
+        let mut coverage: BTreeMap<String, BTreeMap<usize, u64>> = BTreeMap::new();
+        let result = get_coverage(
+            Path::new("/no/such/crate"),
+            Path::new("/no/such/crate/lcov.info"),
+            &mut coverage,
+        );
+        assert!(result.is_err());
+        assert!(coverage.is_empty());
+    }
+
+    #[test]
+    fn lines_are_listed_as_ranges() {
+        assert_eq!(get_ranges(&[1, 2, 3, 5, 7, 8]), "1-3, 5, 7-8");
+        assert_eq!(get_ranges(&[]), "");
+    }
+}
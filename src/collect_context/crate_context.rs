@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet},
     fs::{self, read_to_string, File},
     io::Write,
     path::PathBuf,
@@ -8,13 +8,25 @@ use std::{
     rc::Rc,
 };
 
-use syn::parse_file;
+use call_chain::{
+    analysis::{
+        exporter::{BrData, CallsAndTypes, MacroDef, PanicSite},
+        sourceinfo::SourceInfo,
+    },
+    utils::get_output_file_name,
+};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{parse_file, spanned::Spanned};
 use toml::Value;
 
 use super::{
-    items_context::MyVisibility,
-    mod_context::{ModContext, ModInfo, ModModInfo},
-    result::{FnData, StructData},
+    config::ContextConfig,
+    coverage::{get_coverage, get_fn_coverage, FnCoverage},
+    items_context::{ImplItem, MacroItem, MyVisibility, SourceLocation, UseTree},
+    mod_context::{get_span_file, ModContext, ModInfo, ModModInfo},
+    result::{FnData, ManifestEntry, StructData, StructType},
+    syntax_context::get_tests,
 };
 
 #[derive(Debug, Clone)]
@@ -23,6 +35,19 @@ pub struct CrateContext {
     crate_path: PathBuf,
     entry_file_paths: Vec<PathBuf>,
     main_mod_contexts: Vec<Rc<RefCell<ModContext>>>,
+    config: ContextConfig,
+    tests: BTreeMap<String, Vec<String>>,
+    constructors: BTreeMap<String, Vec<(usize, usize, String)>>,
+    coverage: BTreeMap<String, BTreeMap<usize, u64>>,
+    manifest: RefCell<BTreeMap<String, ManifestEntry>>,
+    source_files: BTreeMap<String, String>,
+    // a crate with both `src/main.rs` and `src/lib.rs` has two mod trees named after the crate,
+    // so the mods are kept by the entry file of their target
+    mod_visibilities: BTreeMap<(PathBuf, String), MyVisibility>,
+    // def paths re-exported by a `pub use` in a reachable mod, with the path they are
+    // re-exported at, a glob re-export is kept by the path of the mod it takes items from
+    re_exports: BTreeMap<String, String>,
+    glob_re_exports: BTreeMap<String, String>,
 }
 
 impl CrateContext {
@@ -32,6 +57,15 @@ impl CrateContext {
             crate_path: PathBuf::new(),
             entry_file_paths: Vec::new(),
             main_mod_contexts: Vec::new(),
+            config: ContextConfig::new(),
+            tests: BTreeMap::new(),
+            constructors: BTreeMap::new(),
+            coverage: BTreeMap::new(),
+            manifest: RefCell::new(BTreeMap::new()),
+            source_files: BTreeMap::new(),
+            mod_visibilities: BTreeMap::new(),
+            re_exports: BTreeMap::new(),
+            glob_re_exports: BTreeMap::new(),
         };
         let toml_path = crate_path.join("Cargo.toml");
         if fs::exists(&toml_path).unwrap() {
@@ -79,6 +113,7 @@ impl CrateContext {
             let entry_code = read_to_string(entry_file_path).unwrap();
             let entry_syntax = parse_file(&entry_code).unwrap();
             let mut mod_mod_info = ModModInfo::new();
+            mod_mod_info.insert_span_file(&get_span_file(&entry_syntax));
             mod_mod_info.insert_mod_name(&self.crate_name);
             mod_mod_info.insert_parent_mod_tree(&String::new());
             mod_mod_info.insert_file_path(entry_file_path);
@@ -100,6 +135,49 @@ impl CrateContext {
                 .borrow_mut()
                 .add_use_mod(&self.main_mod_contexts[1]);
         }
+        let mut source_files: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for mod_context in self.main_mod_contexts.iter() {
+            mod_context.borrow().get_source_files(&mut source_files);
+        }
+        for (span_file, file_path) in source_files.iter() {
+            let file_path = file_path
+                .strip_prefix(&self.crate_path)
+                .unwrap_or(file_path)
+                .to_string_lossy()
+                .to_string();
+            self.source_files.insert(span_file.clone(), file_path);
+        }
+        for (entry_file_path, mod_context) in self
+            .entry_file_paths
+            .iter()
+            .zip(self.main_mod_contexts.iter())
+        {
+            let mut mod_visibilities: BTreeMap<String, MyVisibility> = BTreeMap::new();
+            mod_context
+                .borrow()
+                .get_mod_visibilities(&mut mod_visibilities);
+            for (mod_tree, visibility) in mod_visibilities {
+                self.mod_visibilities
+                    .insert((entry_file_path.clone(), mod_tree), visibility);
+            }
+        }
+    }
+
+    pub fn get_source_location<T: ToTokens>(&self, item: &T) -> Option<SourceLocation> {
+        let file_path = self.source_files.get(&item.span().file())?;
+        Some(SourceLocation::new(item, file_path))
+    }
+
+    pub fn get_source_info<T: ToTokens>(&self, item: &T) -> Option<SourceInfo> {
+        let span = item.span();
+        let file_path = self.source_files.get(&span.file())?;
+        Some(SourceInfo::new(
+            file_path,
+            span.start().line,
+            span.start().column + 1,
+            span.end().line,
+            span.end().column + 1,
+        ))
     }
 
     fn change_impl_name(&mut self) {}
@@ -116,14 +194,74 @@ impl CrateContext {
         for mod_context in self.main_mod_contexts.iter_mut() {
             ModContext::change_impl_name_recursively(&mod_context);
         }
+        self.collect_re_exports();
+    }
+
+    fn collect_re_exports(&mut self) {
+        let mut pub_uses: Vec<(String, UseTree)> = Vec::new();
+        for mod_context in self.main_mod_contexts.iter() {
+            mod_context.borrow().get_pub_uses(&mut pub_uses);
+        }
+        for (mod_tree, use_tree) in pub_uses.iter() {
+            if !matches!(use_tree.get_visibility(), MyVisibility::PubT)
+                || !self.is_mod_reachable(mod_tree)
+            {
+                continue;
+            }
+            let use_path = use_tree.get_use_tree().to_string();
+            let (re_exports, def_path, public_path) = if use_tree.get_name().eq("*") {
+                (
+                    &mut self.glob_re_exports,
+                    use_path.trim_end_matches("::*").to_string(),
+                    mod_tree.clone(),
+                )
+            } else {
+                let use_name = match use_tree.get_alias() {
+                    Some(alias) => alias,
+                    None => use_tree.get_name(),
+                };
+                (
+                    &mut self.re_exports,
+                    use_path,
+                    mod_tree.clone() + "::" + use_name,
+                )
+            };
+            // the shortest path an item is re-exported at is kept
+            match re_exports.get(&def_path) {
+                Some(has_public_path) if has_public_path.len() <= public_path.len() => {}
+                _ => {
+                    re_exports.insert(def_path, public_path);
+                }
+            }
+        }
+    }
+
+    // the path an item can be named at from outside the crate, it is the def path if every
+    // mod above the item is `pub`, else the path of a `pub use` that re-exports the item
+    // or the mod it is defined in
+    pub fn get_public_path(&self, def_path: &str) -> Option<String> {
+        let (parent_mod_tree, name) = def_path.rsplit_once("::")?;
+        if self.is_mod_reachable(parent_mod_tree) {
+            return Some(def_path.to_string());
+        }
+        if let Some(public_path) = self.re_exports.get(def_path) {
+            return Some(public_path.clone());
+        }
+        let public_mod_tree = self
+            .glob_re_exports
+            .get(parent_mod_tree)
+            .or(self.re_exports.get(parent_mod_tree))?;
+        Some(public_mod_tree.clone() + "::" + name)
     }
 
     pub fn parse_all_context(
         &self,
         mod_trees: &Vec<String>,
-        fns: &HashMap<String, FnData>,
-        structs: &HashMap<String, StructData>,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
     ) {
+        // every written context appends its record to the index
+        File::create(self.crate_path.join("rfocxt/index.jsonl")).unwrap();
         for mod_context in self.main_mod_contexts.iter() {
             mod_context.borrow().get_all_context(
                 &self.crate_path.join("rfocxt"),
@@ -133,6 +271,45 @@ impl CrateContext {
                 self,
             );
         }
+        let mut file = File::create(self.crate_path.join("rfocxt/manifest.json")).unwrap();
+        file.write_all(
+            serde_json::to_string_pretty(&*self.manifest.borrow())
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+    }
+
+    // the calls and types call_chain writes from the MIR are written from the syntax of each fn
+    pub fn write_syntax_calls(
+        &self,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
+    ) {
+        let output_path = self.crate_path.join("rfocxt");
+        let _ = fs::remove_dir_all(output_path.join("callsandtypes"));
+        fs::create_dir_all(output_path.join("callsandtypes")).unwrap();
+        let mut fn_output_names: BTreeMap<String, String> = BTreeMap::new();
+        for mod_context in self.main_mod_contexts.iter() {
+            mod_context
+                .borrow()
+                .get_fn_output_names(&mut fn_output_names);
+        }
+        for mod_context in self.main_mod_contexts.iter() {
+            mod_context.borrow().write_syntax_calls(
+                &output_path,
+                fns,
+                structs,
+                &fn_output_names,
+                self,
+            );
+        }
+    }
+
+    pub fn insert_manifest_entry(&self, complete_fn_name: &str, manifest_entry: ManifestEntry) {
+        self.manifest
+            .borrow_mut()
+            .insert(complete_fn_name.to_string(), manifest_entry);
     }
 
     pub fn cout_in_one_file_for_test(&self) {
@@ -142,7 +319,7 @@ impl CrateContext {
         file.write_all(format!("{:#?}", self).as_bytes()).unwrap();
     }
 
-    pub fn cout_all_mod_trees_in_on_file_for_test(&self, out_mod_trees: &mut HashSet<String>) {
+    pub fn cout_all_mod_trees_in_on_file_for_test(&self, out_mod_trees: &mut BTreeSet<String>) {
         let output_path = self.crate_path.join("rfocxt/mod_trees");
         fs::create_dir_all(&output_path).unwrap();
         let mut num = 0;
@@ -180,19 +357,378 @@ impl CrateContext {
 
     pub fn get_result(
         &self,
-        fns: &mut HashMap<String, FnData>,
-        structs: &mut HashMap<String, StructData>,
+        fns: &mut BTreeMap<String, FnData>,
+        structs: &mut BTreeMap<String, StructData>,
     ) {
         for main_mod_context in self.main_mod_contexts.iter() {
             main_mod_context.borrow().get_result(fns, structs);
         }
+        self.get_macro_result(structs);
     }
 
-    pub fn get_relative_types_for_struct(&self, name: &String, relative_types: &mut Vec<String>) {
+    // the items expanded from macro invocations are found by the place of the invocation
+    fn get_macro_result(&self, structs: &mut BTreeMap<String, StructData>) {
+        let macro_defs_path = self.crate_path.join("rfocxt/macro_items");
+        let entries = match fs::read_dir(macro_defs_path) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let mut macro_items: Vec<MacroItem> = Vec::new();
         for main_mod_context in self.main_mod_contexts.iter() {
-            main_mod_context
-                .borrow()
-                .get_relative_types_for_struct(name, relative_types);
+            main_mod_context.borrow().get_macro_items(&mut macro_items);
+        }
+        let mut macro_rules: BTreeMap<String, MacroItem> = BTreeMap::new();
+        for macro_item in macro_items.iter() {
+            if let Some(rules_name) = macro_item.get_rules_name() {
+                macro_rules.insert(rules_name, macro_item.clone());
+            }
+        }
+        for entry in entries {
+            let contents = read_to_string(entry.unwrap().path()).unwrap();
+            let macro_def: MacroDef = match serde_json::from_str(&contents) {
+                Ok(macro_def) => macro_def,
+                Err(_) => continue,
+            };
+            let call_site = &macro_def.call_site;
+            for macro_item in macro_items.iter() {
+                if macro_item.is_rules() {
+                    continue;
+                }
+                let source_location = match self.get_source_location(macro_item.get_item()) {
+                    Some(source_location) => source_location,
+                    None => continue,
+                };
+                if !source_location.contains(&call_site.get_file(), call_site.get_startline()) {
+                    continue;
+                }
+                let macro_item = get_invoked_macro_item(macro_item, &macro_rules);
+                let struct_data = StructData {
+                    struct_name: macro_def.def.split("::").last().unwrap().to_string(),
+                    complete_struct_name: macro_def.def.clone(),
+                    struct_type: StructType::Macro(macro_item),
+                };
+                // a written out item of the same name is kept
+                if !structs.contains_key(&macro_def.def) {
+                    structs.insert(macro_def.def.clone(), struct_data);
+                }
+                break;
+            }
+        }
+    }
+
+    // without call_chain the items a macro invocation expands to are guessed from its tokens,
+    // the names after an item keyword and the names passed as bare args
+    pub fn get_syntax_macro_result(&self, structs: &mut BTreeMap<String, StructData>) {
+        let mut macro_items: Vec<MacroItem> = Vec::new();
+        for main_mod_context in self.main_mod_contexts.iter() {
+            main_mod_context.borrow().get_macro_items(&mut macro_items);
+        }
+        let mut macro_rules: BTreeMap<String, MacroItem> = BTreeMap::new();
+        for macro_item in macro_items.iter() {
+            if let Some(rules_name) = macro_item.get_rules_name() {
+                macro_rules.insert(rules_name, macro_item.clone());
+            }
+        }
+        for macro_item in macro_items.iter() {
+            if macro_item.is_rules() {
+                continue;
+            }
+            let tokens: Vec<TokenTree> = macro_item
+                .get_item()
+                .mac
+                .tokens
+                .clone()
+                .into_iter()
+                .collect();
+            let is_separator = |token: Option<&TokenTree>| match token {
+                None => true,
+                Some(TokenTree::Punct(punct)) => punct.as_char() == ',',
+                Some(_) => false,
+            };
+            let mut names: Vec<String> = Vec::new();
+            for (index, token) in tokens.iter().enumerate() {
+                if let TokenTree::Ident(ident) = token {
+                    let before = index.checked_sub(1).and_then(|index| tokens.get(index));
+                    if is_separator(before) && is_separator(tokens.get(index + 1)) {
+                        names.push(ident.to_string());
+                    }
+                }
+            }
+            get_item_names(&macro_item.get_item().mac.tokens, &mut names);
+            for name in names.iter() {
+                let complete_name = macro_item.get_mod_tree().to_string() + "::" + name;
+                // a written out item of the same name is kept
+                if structs.contains_key(&complete_name) {
+                    continue;
+                }
+                let struct_data = StructData {
+                    struct_name: name.clone(),
+                    complete_struct_name: complete_name.clone(),
+                    struct_type: StructType::Macro(get_invoked_macro_item(
+                        macro_item,
+                        &macro_rules,
+                    )),
+                };
+                structs.insert(complete_name, struct_data);
+            }
+        }
+    }
+
+    pub fn insert_config(&mut self, config: &ContextConfig) {
+        self.config = config.clone();
+    }
+
+    pub fn get_config(&self) -> &ContextConfig {
+        &self.config
+    }
+
+    pub fn collect_tests(&mut self) {
+        get_tests(&self.crate_path.join("rfocxt"), &mut self.tests);
+    }
+
+    // the constructors of every type are found once, not for every context
+    pub fn collect_constructors(&mut self, structs: &BTreeMap<String, StructData>) {
+        let mut constructors: BTreeMap<String, Vec<(usize, usize, String)>> = BTreeMap::new();
+        for main_mod_context in self.main_mod_contexts.iter() {
+            main_mod_context.borrow().get_constructors(
+                &self.crate_name,
+                structs,
+                &mut constructors,
+            );
         }
+        self.constructors = constructors;
+    }
+
+    pub fn get_constructors(&self, type_name: &str) -> Vec<(usize, usize, String)> {
+        self.constructors
+            .get(type_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_tests_for_fn(&self, complete_fn_name_in_file: &String) -> Vec<String> {
+        match self.tests.get(complete_fn_name_in_file) {
+            Some(tests) => tests
+                .iter()
+                .take(self.config.get_max_tests())
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn collect_coverage(&mut self, coverage_path: &PathBuf) -> Result<(), String> {
+        get_coverage(&self.crate_path, coverage_path, &mut self.coverage)
+    }
+
+    pub fn get_fn_coverage(
+        &self,
+        complete_fn_name_in_file: &String,
+        data: &CallsAndTypes,
+    ) -> Option<FnCoverage> {
+        if self.coverage.is_empty() {
+            return None;
+        }
+        let fn_source = data.fn_source.as_ref()?;
+        let br_file = self
+            .crate_path
+            .join("rfocxt/branches")
+            .join(get_output_file_name(complete_fn_name_in_file) + ".json");
+        let br_data: Option<BrData> = match read_to_string(br_file) {
+            Ok(contents) => serde_json::from_str(&contents).ok(),
+            Err(_) => None,
+        };
+        Some(get_fn_coverage(
+            &self.crate_path,
+            fn_source,
+            &br_data,
+            &self.coverage,
+        ))
+    }
+
+    pub fn get_panic_sites(&self, complete_fn_name_in_file: &String) -> Vec<PanicSite> {
+        let panic_file = self
+            .crate_path
+            .join("rfocxt/panics")
+            .join(get_output_file_name(complete_fn_name_in_file) + ".json");
+        match read_to_string(panic_file) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn get_lib_path(&self) -> PathBuf {
+        self.crate_path.join("src/lib.rs")
+    }
+
+    // a mod tree in both targets is the one of the lib, which tests are written against
+    pub fn get_mod_visibility(&self, mod_tree: &str) -> MyVisibility {
+        let lib_path = self.get_lib_path();
+        let mut entry_file_paths: Vec<&PathBuf> = self.entry_file_paths.iter().collect();
+        entry_file_paths.sort_by_key(|entry_file_path| **entry_file_path != lib_path);
+        for entry_file_path in entry_file_paths {
+            let key = (entry_file_path.clone(), mod_tree.to_string());
+            if let Some(visibility) = self.mod_visibilities.get(&key) {
+                return visibility.clone();
+            }
+        }
+        MyVisibility::Pri
+    }
+
+    // a mod is reachable from outside the crate if it is in the lib and it and every mod
+    // above it is `pub`
+    pub fn is_mod_reachable(&self, mod_tree: &str) -> bool {
+        let lib_path = self.get_lib_path();
+        let mut mod_path = String::new();
+        for segment in mod_tree.split("::") {
+            if !mod_path.is_empty() {
+                mod_path += "::";
+            }
+            mod_path += segment;
+            let key = (lib_path.clone(), mod_path.clone());
+            if !matches!(self.mod_visibilities.get(&key), Some(MyVisibility::PubT)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn get_crate_name(&self) -> String {
+        self.crate_name.clone()
+    }
+
+    pub fn get_impls_for_struct(&self, name: &String, impls: &mut Vec<ImplItem>) {
+        for main_mod_context in self.main_mod_contexts.iter() {
+            main_mod_context.borrow().get_impls_for_struct(name, impls);
+        }
+    }
+}
+
+// a macro invocation carries the `macro_rules!` it invokes, if the crate defines it
+fn get_invoked_macro_item(
+    macro_item: &MacroItem,
+    macro_rules: &BTreeMap<String, MacroItem>,
+) -> MacroItem {
+    let mut macro_item = macro_item.clone();
+    let invoked_name = macro_item.get_macro_name().split("::").last().unwrap();
+    if let Some(rules) = macro_rules.get(invoked_name) {
+        macro_item.insert_rules(rules.get_item());
+    }
+    macro_item
+}
+
+fn get_item_names(tokens: &TokenStream, names: &mut Vec<String>) {
+    let mut after_keyword = false;
+    for token in tokens.clone() {
+        match &token {
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                if after_keyword && !name.eq("mut") {
+                    names.push(name);
+                    after_keyword = false;
+                } else {
+                    after_keyword = matches!(
+                        name.as_str(),
+                        "fn" | "static" | "const" | "struct" | "enum" | "union" | "type" | "trait"
+                    );
+                }
+            }
+            TokenTree::Group(group) => {
+                get_item_names(&group.stream(), names);
+                after_keyword = false;
+            }
+            _ => after_keyword = false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    fn get_crate_context(dir_name: &str, files: &[(&str, &str)]) -> CrateContext {
+        let crate_path = temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&crate_path);
+        fs::create_dir_all(crate_path.join("src")).unwrap();
+        fs::write(
+            crate_path.join("Cargo.toml"),
+            "[package]\nname = \"sample\"\n",
+        )
+        .unwrap();
+        for (file_name, code) in files.iter() {
+            fs::write(crate_path.join(file_name), code).unwrap();
+        }
+        let mut crate_context = CrateContext::new(&crate_path);
+        crate_context.parse_crate();
+        crate_context
+    }
+
+    #[test]
+    fn mods_of_the_lib_and_the_bin_are_kept_apart() {
+        let crate_context = get_crate_context(
+            "rfocxt_mod_visibilities",
+            &[
+                ("src/lib.rs", "mod util { pub fn double() {} }"),
+                (
+                    "src/main.rs",
+                    "pub mod util { pub fn triple() {} } pub mod cli { pub fn run() {} } fn main() {}",
+                ),
+            ],
+        );
+        // the private `util` of the lib is not overwritten by the `pub util` of the bin
+        assert_eq!(
+            crate_context.get_mod_visibility("sample::util"),
+            MyVisibility::PubS
+        );
+        assert!(!crate_context.is_mod_reachable("sample::util"));
+        // mods only in the bin keep their visibility but are never reachable
+        assert_eq!(
+            crate_context.get_mod_visibility("sample::cli"),
+            MyVisibility::PubT
+        );
+        assert!(!crate_context.is_mod_reachable("sample::cli"));
+        assert!(crate_context.is_mod_reachable("sample"));
+    }
+
+    #[test]
+    fn constructors_are_ranked_by_kind() {
+        let mut crate_context = get_crate_context(
+            "rfocxt_constructors",
+            &[(
+                "src/lib.rs",
+                "pub struct Point { x: i32 }
+impl Point {
+    pub fn new(x: i32) -> Self { Point { x } }
+    pub fn moved(&self, dx: i32) -> Point { Point { x: self.x + dx } }
+}
+impl Default for Point {
+    fn default() -> Self { Point { x: 0 } }
+}
+impl From<i32> for Point {
+    fn from(x: i32) -> Self { Point { x } }
+}
+pub fn origin() -> Point { Point::default() }
+",
+            )],
+        );
+        crate_context.change_all_names();
+        let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+        let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
+        crate_context.get_result(&mut fns, &mut structs);
+        crate_context.collect_constructors(&structs);
+        let mut constructors = crate_context.get_constructors("sample::Point");
+        constructors.sort_by_key(|(rank, param_num, _)| (*rank, *param_num));
+        assert_eq!(
+            constructors,
+            vec![
+                (1, 0, "Point::default()".to_string()),
+                (1, 1, "Point::new(x: i32) -> Self".to_string()),
+                (2, 1, "Point::from(value: i32)".to_string()),
+                (3, 0, "sample::origin() -> Point".to_string()),
+                (3, 2, "Point::moved(&self, dx: i32) -> Point".to_string()),
+            ]
+        );
+        assert!(crate_context.get_constructors("sample::Line").is_empty());
     }
 }
@@ -0,0 +1,264 @@
+use syn::{Attribute, Expr, Lit, Meta};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doctest {
+    code: String,
+    ignore: bool,
+    no_run: bool,
+    should_panic: bool,
+    compile_fail: bool,
+}
+
+impl Doctest {
+    pub fn get_code(&self) -> String {
+        self.code.clone()
+    }
+
+    pub fn get_marks(&self) -> Vec<String> {
+        let mut marks: Vec<String> = Vec::new();
+        if self.ignore {
+            marks.push("ignore".to_string());
+        }
+        if self.no_run {
+            marks.push("no_run".to_string());
+        }
+        if self.should_panic {
+            marks.push("should_panic".to_string());
+        }
+        if self.compile_fail {
+            marks.push("compile_fail".to_string());
+        }
+        marks
+    }
+}
+
+pub fn get_doc_string(attrs: &[Attribute]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for attr in attrs.iter() {
+        if let Meta::NameValue(name_value) = &attr.meta {
+            if !name_value.path.is_ident("doc") {
+                continue;
+            }
+            if let Expr::Lit(lit) = &name_value.value {
+                if let Lit::Str(lit_str) = &lit.lit {
+                    for line in lit_str.value().lines() {
+                        lines.push(line.to_string());
+                    }
+                }
+            }
+        }
+    }
+    // rustdoc removes the indentation shared by all non-empty lines, only spaces and tabs
+    // count, so the indentation is always made of whole chars
+    let get_indent = |line: &String| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(get_indent)
+        .min()
+        .unwrap_or(0);
+    let mut doc = String::new();
+    for line in lines.iter() {
+        doc += &line[get_indent(line).min(indent)..];
+        doc += "\n";
+    }
+    doc
+}
+
+// like rustdoc, a fence with an unknown attribute is not rust unless it also says `rust`
+fn parse_fence_info(info: &str, doctest: &mut Doctest) -> bool {
+    let mut has_rust = false;
+    let mut has_other = false;
+    for token in info
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+    {
+        match token {
+            "rust" => has_rust = true,
+            "ignore" => doctest.ignore = true,
+            "no_run" => doctest.no_run = true,
+            "should_panic" => doctest.should_panic = true,
+            "compile_fail" => doctest.compile_fail = true,
+            "test_harness" | "standalone" => {}
+            _ if token.starts_with("ignore-") => doctest.ignore = true,
+            _ if token.starts_with("edition") => {}
+            _ if token.starts_with('E') && token.len() == 5 => {}
+            _ => has_other = true,
+        }
+    }
+    has_rust || !has_other
+}
+
+fn normalize_code(lines: &[String]) -> String {
+    let mut visible_lines: Vec<String> = Vec::new();
+    for line in lines.iter() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("##") {
+            let indent = &line[..line.len() - trimmed.len()];
+            visible_lines.push(indent.to_string() + &trimmed[1..]);
+        } else if trimmed.eq("#") {
+            visible_lines.push(String::new());
+        } else if let Some(stripped) = trimmed.strip_prefix("# ") {
+            let indent = &line[..line.len() - trimmed.len()];
+            visible_lines.push(indent.to_string() + stripped);
+        } else {
+            visible_lines.push(line.clone());
+        }
+    }
+    let code = visible_lines.join("\n");
+    if code.contains("fn main") {
+        return code;
+    }
+    // like rustdoc, crate attributes and `extern crate` stay outside of the implicit `fn main`
+    let mut crate_lines: Vec<String> = Vec::new();
+    let mut main_lines: Vec<String> = Vec::new();
+    for line in visible_lines.iter() {
+        let trimmed = line.trim_start();
+        if main_lines.is_empty()
+            && (trimmed.starts_with("#![") || trimmed.starts_with("extern crate"))
+        {
+            crate_lines.push(line.clone());
+        } else {
+            main_lines.push(line.clone());
+        }
+    }
+    let mut normalized = String::new();
+    for line in crate_lines.iter() {
+        normalized += line;
+        normalized += "\n";
+    }
+    normalized += "fn main() {\n";
+    for line in main_lines.iter() {
+        if !line.is_empty() {
+            normalized += "    ";
+            normalized += line;
+        }
+        normalized += "\n";
+    }
+    normalized += "}";
+    normalized
+}
+
+pub fn parse_doctests(doc: &str) -> Vec<Doctest> {
+    let mut doctests: Vec<Doctest> = Vec::new();
+    let mut lines = doc.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let fence = if trimmed.starts_with("```") {
+            "```"
+        } else if trimmed.starts_with("~~~") {
+            "~~~"
+        } else {
+            continue;
+        };
+        let mut doctest = Doctest {
+            code: String::new(),
+            ignore: false,
+            no_run: false,
+            should_panic: false,
+            compile_fail: false,
+        };
+        let is_rust = parse_fence_info(
+            trimmed.trim_start_matches(fence.chars().next().unwrap()),
+            &mut doctest,
+        );
+        let mut code_lines: Vec<String> = Vec::new();
+        for code_line in lines.by_ref() {
+            if code_line.trim_start().starts_with(fence) {
+                break;
+            }
+            code_lines.push(code_line.to_string());
+        }
+        if is_rust {
+            doctest.code = normalize_code(&code_lines);
+            doctests.push(doctest);
+        }
+    }
+    doctests
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, Attribute};
+
+    use super::*;
+
+    #[test]
+    fn doc_string_removes_shared_indentation() {
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[doc = "  first"]),
+            parse_quote!(#[doc = " "]),
+            parse_quote!(#[doc = "    second"]),
+        ];
+        assert_eq!(get_doc_string(&attrs), "first\n\n  second\n");
+    }
+
+    #[test]
+    fn doc_string_keeps_non_ascii_whitespace() {
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[doc = " \u{3000}wide"]),
+            parse_quote!(#[doc = " \u{a0}"]),
+            parse_quote!(#[doc = " narrow"]),
+        ];
+        assert_eq!(get_doc_string(&attrs), "\u{3000}wide\n\u{a0}\nnarrow\n");
+    }
+
+    #[test]
+    fn doctests_are_read_from_rust_fences() {
+        let doc = String::from(
+            "text\n```\nlet a = 1;\n```\n```text\nnot rust\n```\n~~~rust,no_run,should_panic\nlet b = 2;\n~~~\n",
+        );
+        let doctests = parse_doctests(&doc);
+        assert_eq!(doctests.len(), 2);
+        assert_eq!(doctests[0].get_code(), "fn main() {\n    let a = 1;\n}");
+        assert!(doctests[0].get_marks().is_empty());
+        assert_eq!(doctests[1].get_code(), "fn main() {\n    let b = 2;\n}");
+        assert_eq!(doctests[1].get_marks(), vec!["no_run", "should_panic"]);
+    }
+
+    #[test]
+    fn doctests_show_hidden_lines() {
+        let doc =
+            String::from("```ignore\n# use std::mem;\n#\nlet a = 1;\n    ## not hidden\n```\n");
+        let doctests = parse_doctests(&doc);
+        assert_eq!(doctests.len(), 1);
+        assert_eq!(
+            doctests[0].get_code(),
+            "fn main() {\n    use std::mem;\n\n    let a = 1;\n        # not hidden\n}"
+        );
+        assert_eq!(doctests[0].get_marks(), vec!["ignore"]);
+    }
+
+    #[test]
+    fn hidden_lines_keep_their_indentation() {
+        let doc = String::from("```\nfn main() {\n    # let a = 1;\n    #   let b = 2;\n}\n```\n");
+        let doctests = parse_doctests(&doc);
+        assert_eq!(
+            doctests[0].get_code(),
+            "fn main() {\n    let a = 1;\n      let b = 2;\n}"
+        );
+    }
+
+    #[test]
+    fn fences_with_unknown_attributes_are_rust_only_if_they_say_so() {
+        let doc = String::from(
+            "```rust,foo\nlet a = 1;\n```\n```foo\nnot rust\n```\n``` rust  no_run\nlet b = 2;\n```\n",
+        );
+        let doctests = parse_doctests(&doc);
+        assert_eq!(doctests.len(), 2);
+        assert_eq!(doctests[0].get_code(), "fn main() {\n    let a = 1;\n}");
+        assert!(doctests[0].get_marks().is_empty());
+        assert_eq!(doctests[1].get_code(), "fn main() {\n    let b = 2;\n}");
+        assert_eq!(doctests[1].get_marks(), vec!["no_run"]);
+    }
+
+    #[test]
+    fn doctests_keep_crate_attributes_outside_of_main() {
+        let doc = String::from("```\n#![allow(unused)]\nextern crate core;\nlet a = 1;\n```\n");
+        let doctests = parse_doctests(&doc);
+        assert_eq!(
+            doctests[0].get_code(),
+            "#![allow(unused)]\nextern crate core;\nfn main() {\n    let a = 1;\n}"
+        );
+    }
+}
@@ -1,13 +1,22 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
+use quote::ToTokens;
+use serde::{Serialize, Serializer};
 use syn::{
-    ImplItemConst, ImplItemFn, ImplItemType, Item, ItemConst, ItemEnum, ItemFn, ItemImpl, ItemMod,
-    ItemStatic, ItemStruct, ItemTrait, ItemTraitAlias, ItemType, ItemUnion, ItemUse,
-    TraitItemConst, TraitItemFn, TraitItemType,
+    parse_str, spanned::Spanned, Block, ForeignItem, ForeignItemFn, ForeignItemStatic,
+    GenericArgument, Generics, ImplItemConst, ImplItemFn, ImplItemType, Item, ItemConst, ItemEnum,
+    ItemFn, ItemForeignMod, ItemImpl, ItemMacro, ItemMod, ItemStatic, ItemStruct, ItemTrait,
+    ItemTraitAlias, ItemType, ItemUnion, ItemUse, Lit, LitStr, PathArguments, Token,
+    TraitItemConst, TraitItemFn, TraitItemType, Type, TypeParamBound,
 };
 
 use super::mod_context::ModContext;
 
+// the body of a fn kept for its signature only, it type-checks whatever the fn returns
+pub fn get_cleared_block() -> Block {
+    parse_str("{ unimplemented!() }").unwrap()
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum MyVisibility {
     PubT,
@@ -16,6 +25,108 @@ pub enum MyVisibility {
     Pri,
 }
 
+impl MyVisibility {
+    pub fn get_label(&self) -> String {
+        match self {
+            MyVisibility::PubT => "pub".to_string(),
+            MyVisibility::PubS => "pub(crate)".to_string(),
+            MyVisibility::PubI(path) => {
+                let path = path.to_string();
+                if path.eq("super") || path.eq("self") {
+                    format!("pub({})", path)
+                } else {
+                    format!("pub(in {})", path)
+                }
+            }
+            MyVisibility::Pri => "private".to_string(),
+        }
+    }
+}
+
+impl Serialize for MyVisibility {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.get_label())
+    }
+}
+
+// the source text of an item with its span in the file it was parsed from
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemSource {
+    source: String,
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl ItemSource {
+    pub fn new<T: ToTokens>(item: &T) -> Self {
+        let span = item.span();
+        ItemSource {
+            source: span
+                .source_text()
+                .unwrap_or(item.to_token_stream().to_string()),
+            start_line: span.start().line,
+            start_column: span.start().column,
+            end_line: span.end().line,
+            end_column: span.end().column,
+        }
+    }
+}
+
+// where an item is in the crate, in the same form as `SourceInfo` of the MIR side
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceLocation {
+    file_path: String,
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl SourceLocation {
+    pub fn new<T: ToTokens>(item: &T, file_path: &str) -> Self {
+        let span = item.span();
+        SourceLocation {
+            file_path: file_path.to_string(),
+            start_line: span.start().line,
+            start_column: span.start().column + 1,
+            end_line: span.end().line,
+            end_column: span.end().column + 1,
+        }
+    }
+
+    pub fn contains(&self, file_path: &str, line: usize) -> bool {
+        self.file_path.eq(file_path) && self.start_line <= line && line <= self.end_line
+    }
+
+    pub fn to_comment(&self) -> String {
+        if self.start_line == self.end_line {
+            format!("// {}:{}", self.file_path, self.start_line)
+        } else {
+            format!(
+                "// {}:{}-{}",
+                self.file_path, self.start_line, self.end_line
+            )
+        }
+    }
+}
+
+fn serialize_item<T: ToTokens, S: Serializer>(
+    item: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    item.as_ref().map(ItemSource::new).serialize(serializer)
+}
+
+fn serialize_items<S: Serializer>(items: &[Item], serializer: S) -> Result<S::Ok, S::Error> {
+    items
+        .iter()
+        .map(ItemSource::new)
+        .collect::<Vec<ItemSource>>()
+        .serialize(serializer)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MyPath {
     name: String,
@@ -306,7 +417,13 @@ impl MyPath {
     // }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Serialize for MyPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Name {
     name: String,
     complete_name: String,
@@ -410,6 +527,288 @@ impl Name {
     }
 }
 
+// the self type of an impl block, the paths in it are resolved like the struct names of impls
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelfType {
+    Path(Name, Vec<SelfType>),
+    Reference(bool, Box<SelfType>),
+    Pointer(bool, Box<SelfType>),
+    Slice(Box<SelfType>),
+    Array(Box<SelfType>, String),
+    Tuple(Vec<SelfType>),
+    TraitObject(Name),
+    Param(String),
+    Other(String),
+}
+
+impl SelfType {
+    pub fn new(ty: &Type, generics: &Generics) -> Self {
+        match ty {
+            Type::Path(type_path) if type_path.qself.is_none() => {
+                let segments = &type_path.path.segments;
+                let last_segment = segments.last().unwrap();
+                if segments.len() == 1 && last_segment.arguments.is_none() {
+                    for type_param in generics.type_params() {
+                        if type_param.ident.eq(&last_segment.ident) {
+                            return SelfType::Param(type_param.ident.to_string());
+                        }
+                    }
+                }
+                let mut name = Name::new(&last_segment.ident.to_string());
+                let mut import_names: Vec<String> = Vec::new();
+                for segment in segments.iter() {
+                    import_names.push(segment.ident.to_string());
+                }
+                name.insert_import_name(&import_names.join("::"));
+                let mut args: Vec<SelfType> = Vec::new();
+                if let PathArguments::AngleBracketed(angle_bracketed) = &last_segment.arguments {
+                    for arg in angle_bracketed.args.iter() {
+                        if let GenericArgument::Type(arg_ty) = arg {
+                            args.push(SelfType::new(arg_ty, generics));
+                        }
+                    }
+                }
+                SelfType::Path(name, args)
+            }
+            Type::Reference(type_reference) => SelfType::Reference(
+                type_reference.mutability.is_some(),
+                Box::new(SelfType::new(&type_reference.elem, generics)),
+            ),
+            Type::Ptr(type_ptr) => SelfType::Pointer(
+                type_ptr.mutability.is_some(),
+                Box::new(SelfType::new(&type_ptr.elem, generics)),
+            ),
+            Type::Slice(type_slice) => {
+                SelfType::Slice(Box::new(SelfType::new(&type_slice.elem, generics)))
+            }
+            Type::Array(type_array) => SelfType::Array(
+                Box::new(SelfType::new(&type_array.elem, generics)),
+                type_array.len.to_token_stream().to_string(),
+            ),
+            Type::Tuple(type_tuple) => {
+                let mut elems: Vec<SelfType> = Vec::new();
+                for elem in type_tuple.elems.iter() {
+                    elems.push(SelfType::new(elem, generics));
+                }
+                SelfType::Tuple(elems)
+            }
+            Type::TraitObject(type_trait_object) => {
+                for bound in type_trait_object.bounds.iter() {
+                    if let TypeParamBound::Trait(trait_bound) = bound {
+                        let segments = &trait_bound.path.segments;
+                        let mut name = Name::new(&segments.last().unwrap().ident.to_string());
+                        let mut import_names: Vec<String> = Vec::new();
+                        for segment in segments.iter() {
+                            import_names.push(segment.ident.to_string());
+                        }
+                        name.insert_import_name(&import_names.join("::"));
+                        return SelfType::TraitObject(name);
+                    }
+                }
+                SelfType::Other(ty.to_token_stream().to_string())
+            }
+            Type::Paren(type_paren) => SelfType::new(&type_paren.elem, generics),
+            Type::Group(type_group) => SelfType::new(&type_group.elem, generics),
+            _ => SelfType::Other(ty.to_token_stream().to_string()),
+        }
+    }
+
+    // names that can not be resolved are kept as they are written
+    pub fn change_names(&mut self, mod_context: &Rc<RefCell<ModContext>>) {
+        match self {
+            SelfType::Path(name, args) => {
+                let original_name = name.clone();
+                name.change_name_for_impl_struct_name(mod_context);
+                if name.get_import_name().to_string().eq("") {
+                    *name = original_name;
+                }
+                for arg in args.iter_mut() {
+                    arg.change_names(mod_context);
+                }
+            }
+            SelfType::TraitObject(name) => {
+                let original_name = name.clone();
+                name.change_name_for_impl_trait_name(mod_context);
+                if name.get_import_name().to_string().eq("") {
+                    *name = original_name;
+                }
+            }
+            SelfType::Reference(_, elem) | SelfType::Pointer(_, elem) | SelfType::Slice(elem) => {
+                elem.change_names(mod_context);
+            }
+            SelfType::Array(elem, _) => elem.change_names(mod_context),
+            SelfType::Tuple(elems) => {
+                for elem in elems.iter_mut() {
+                    elem.change_names(mod_context);
+                }
+            }
+            SelfType::Param(_) | SelfType::Other(_) => {}
+        }
+    }
+
+    pub fn is_path(&self) -> bool {
+        matches!(self, SelfType::Path(_, _))
+    }
+
+    // `<u8>` for `impl Foo<u8>`, the impls of one struct with different args get different names,
+    // `None` for `impl<T> Foo<T>`
+    pub fn get_concrete_args_string(&self) -> Option<String> {
+        if let SelfType::Path(_, args) = self {
+            if args.iter().any(|arg| !matches!(arg, SelfType::Param(_))) {
+                let args: Vec<String> = args.iter().map(|arg| arg.get_type_string()).collect();
+                return Some(format!("<{}>", args.join(", ")));
+            }
+        }
+        None
+    }
+
+    // the self type of a call as MIR prints it, `None` if it isn't a rust type
+    pub fn from_mir_type(mir_type: &str) -> Option<Self> {
+        let ty = parse_str::<Type>(mir_type).ok()?;
+        Some(SelfType::new(&ty, &Generics::default()))
+    }
+
+    // binds the generic params of the impl to the types of a concrete self type,
+    // fails if the impl does not apply to it
+    pub fn bind(&self, concrete: &SelfType, bindings: &mut BTreeMap<String, String>) -> bool {
+        match (self, concrete) {
+            (SelfType::Param(param), _) => {
+                let concrete_string = concrete.get_short_type_string();
+                match bindings.get(param) {
+                    Some(bound) => bound.eq(&concrete_string),
+                    None => {
+                        bindings.insert(param.clone(), concrete_string);
+                        true
+                    }
+                }
+            }
+            (SelfType::Path(name, args), SelfType::Path(concrete_name, concrete_args)) => {
+                if !name.get_name().eq(&concrete_name.get_name()) {
+                    return false;
+                }
+                if args.is_empty() {
+                    return true;
+                }
+                args.len() == concrete_args.len()
+                    && args
+                        .iter()
+                        .zip(concrete_args.iter())
+                        .all(|(arg, concrete_arg)| arg.bind(concrete_arg, bindings))
+            }
+            (
+                SelfType::Reference(mutability, elem),
+                SelfType::Reference(concrete_mutability, concrete_elem),
+            )
+            | (
+                SelfType::Pointer(mutability, elem),
+                SelfType::Pointer(concrete_mutability, concrete_elem),
+            ) => mutability == concrete_mutability && elem.bind(concrete_elem, bindings),
+            (SelfType::Slice(elem), SelfType::Slice(concrete_elem)) => {
+                elem.bind(concrete_elem, bindings)
+            }
+            (SelfType::Array(elem, len), SelfType::Array(concrete_elem, concrete_len)) => {
+                // a const generic length matches every length
+                let is_const_param = len.parse::<usize>().is_err();
+                (is_const_param || len.eq(concrete_len)) && elem.bind(concrete_elem, bindings)
+            }
+            (SelfType::Tuple(elems), SelfType::Tuple(concrete_elems)) => {
+                elems.len() == concrete_elems.len()
+                    && elems
+                        .iter()
+                        .zip(concrete_elems.iter())
+                        .all(|(elem, concrete_elem)| elem.bind(concrete_elem, bindings))
+            }
+            (SelfType::TraitObject(name), SelfType::TraitObject(concrete_name)) => {
+                name.get_name().eq(&concrete_name.get_name())
+            }
+            (SelfType::Other(other), _) => other.eq(&concrete.get_short_type_string()),
+            _ => false,
+        }
+    }
+
+    // the import names of every struct, enum, union and trait in the self type
+    pub fn get_path_names(&self, path_names: &mut Vec<String>) {
+        match self {
+            SelfType::Path(name, args) => {
+                path_names.push(name.get_import_name().to_string());
+                for arg in args.iter() {
+                    arg.get_path_names(path_names);
+                }
+            }
+            SelfType::TraitObject(name) => path_names.push(name.get_import_name().to_string()),
+            SelfType::Reference(_, elem) | SelfType::Pointer(_, elem) | SelfType::Slice(elem) => {
+                elem.get_path_names(path_names);
+            }
+            SelfType::Array(elem, _) => elem.get_path_names(path_names),
+            SelfType::Tuple(elems) => {
+                for elem in elems.iter() {
+                    elem.get_path_names(path_names);
+                }
+            }
+            SelfType::Param(_) | SelfType::Other(_) => {}
+        }
+    }
+
+    fn get_string(&self, is_short: bool) -> String {
+        match self {
+            SelfType::Path(name, args) => {
+                let mut path = if is_short {
+                    name.get_name()
+                } else {
+                    name.get_import_name().to_string()
+                };
+                if !args.is_empty() {
+                    let args: Vec<String> =
+                        args.iter().map(|arg| arg.get_string(is_short)).collect();
+                    path += &format!("<{}>", args.join(", "));
+                }
+                path
+            }
+            SelfType::Reference(mutability, elem) => match mutability {
+                true => format!("&mut {}", elem.get_string(is_short)),
+                false => format!("&{}", elem.get_string(is_short)),
+            },
+            SelfType::Pointer(mutability, elem) => match mutability {
+                true => format!("*mut {}", elem.get_string(is_short)),
+                false => format!("*const {}", elem.get_string(is_short)),
+            },
+            SelfType::Slice(elem) => format!("[{}]", elem.get_string(is_short)),
+            SelfType::Array(elem, len) => format!("[{}; {}]", elem.get_string(is_short), len),
+            SelfType::Tuple(elems) => {
+                let elems: Vec<String> =
+                    elems.iter().map(|elem| elem.get_string(is_short)).collect();
+                if elems.len() == 1 {
+                    format!("({},)", elems[0])
+                } else {
+                    format!("({})", elems.join(", "))
+                }
+            }
+            SelfType::TraitObject(name) => match is_short {
+                true => format!("dyn {}", name.get_name()),
+                false => format!("dyn {}", name.get_import_name().to_string()),
+            },
+            SelfType::Param(param) => param.clone(),
+            SelfType::Other(other) => other.clone(),
+        }
+    }
+
+    // the self type as it is named in MIR, e.g. `&sample::shapes::Circle`
+    pub fn get_type_string(&self) -> String {
+        self.get_string(false)
+    }
+
+    // the self type with every path cut to its last segment, used to match MIR call names
+    pub fn get_short_type_string(&self) -> String {
+        self.get_string(true)
+    }
+}
+
+impl Serialize for SelfType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.get_type_string())
+    }
+}
+
 // #[derive(Debug, Clone, PartialEq)]
 // pub struct Applications {
 //     applications: Vec<String>,
@@ -464,10 +863,12 @@ impl FunctionItem {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ConstItem {
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemConst>,
     visibility: MyVisibility,
+    mod_tree: MyPath,
 }
 
 impl ConstItem {
@@ -475,6 +876,7 @@ impl ConstItem {
         ConstItem {
             item: None,
             visibility: MyVisibility::Pri,
+            mod_tree: MyPath::none(),
         }
     }
 
@@ -493,12 +895,21 @@ impl ConstItem {
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TraitAliasItem {
     item: Option<ItemTraitAlias>,
     visibility: MyVisibility,
+    mod_tree: MyPath,
 }
 
 impl TraitAliasItem {
@@ -506,6 +917,7 @@ impl TraitAliasItem {
         TraitAliasItem {
             item: None,
             visibility: MyVisibility::Pri,
+            mod_tree: MyPath::none(),
         }
     }
 
@@ -524,12 +936,90 @@ impl TraitAliasItem {
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
+}
+
+// a `name!(...)` invocation at module level, e.g. `lazy_static!` or `thread_local!`,
+// it is kept for the items the invocation expands to, with the `macro_rules!` of the
+// invoked macro if it is defined in the crate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MacroItem {
+    macro_name: String,
+    mod_tree: MyPath,
+    #[serde(serialize_with = "serialize_item")]
+    item: Option<ItemMacro>,
+    #[serde(serialize_with = "serialize_item")]
+    rules: Option<ItemMacro>,
+}
+
+impl MacroItem {
+    pub fn new() -> Self {
+        MacroItem {
+            macro_name: String::new(),
+            mod_tree: MyPath::none(),
+            item: None,
+            rules: None,
+        }
+    }
+
+    pub fn is_rules(&self) -> bool {
+        self.get_item().ident.is_some()
+    }
+
+    // the name a `macro_rules!` definition is invoked with
+    pub fn get_rules_name(&self) -> Option<String> {
+        self.get_item()
+            .ident
+            .as_ref()
+            .map(|ident| ident.to_string())
+    }
+
+    pub fn insert_rules(&mut self, rules: &ItemMacro) {
+        self.rules = Some(rules.clone());
+    }
+
+    pub fn get_rules(&self) -> Option<&ItemMacro> {
+        self.rules.as_ref()
+    }
+
+    pub fn insert_item(&mut self, item: &ItemMacro) {
+        self.macro_name = item.mac.path.to_token_stream().to_string().replace(' ', "");
+        self.item = Some(item.clone());
+    }
+
+    pub fn get_item(&self) -> &ItemMacro {
+        self.item.as_ref().unwrap()
+    }
+
+    pub fn get_macro_name(&self) -> &String {
+        &self.macro_name
+    }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
+
+    pub fn to_item(&self) -> Item {
+        Item::Macro(self.item.clone().unwrap())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct UseItem {
     item: Option<ItemUse>,
     visibility: MyVisibility,
+    mod_tree: MyPath,
 }
 
 impl UseItem {
@@ -537,6 +1027,7 @@ impl UseItem {
         UseItem {
             item: None,
             visibility: MyVisibility::Pri,
+            mod_tree: MyPath::none(),
         }
     }
 
@@ -555,6 +1046,14 @@ impl UseItem {
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -565,6 +1064,7 @@ pub struct ModItem {
     // inline: bool,
     inside_items: Vec<Item>,
     visibility: MyVisibility,
+    mod_tree: MyPath,
 }
 
 impl ModItem {
@@ -575,6 +1075,7 @@ impl ModItem {
             item: None,
             inside_items: Vec::new(),
             visibility: MyVisibility::Pri,
+            mod_tree: MyPath::none(),
         }
     }
 
@@ -635,12 +1136,22 @@ impl ModItem {
     pub fn get_visibility(&self) -> MyVisibility {
         self.visibility.clone()
     }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct StaticItem {
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemStatic>,
     visibility: MyVisibility,
+    mod_tree: MyPath,
 }
 
 impl StaticItem {
@@ -648,6 +1159,7 @@ impl StaticItem {
         StaticItem {
             item: None,
             visibility: MyVisibility::Pri,
+            mod_tree: MyPath::none(),
         }
     }
 
@@ -666,12 +1178,21 @@ impl StaticItem {
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeItem {
     item: Option<ItemType>,
     visibility: MyVisibility,
+    mod_tree: MyPath,
 }
 
 impl TypeItem {
@@ -679,6 +1200,7 @@ impl TypeItem {
         TypeItem {
             item: None,
             visibility: MyVisibility::Pri,
+            mod_tree: MyPath::none(),
         }
     }
 
@@ -697,14 +1219,24 @@ impl TypeItem {
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
+
+    pub fn insert_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct FnItem {
     fn_name: Name,
     complete_name_in_file: String,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemFn>,
     // has_items: bool,
+    #[serde(serialize_with = "serialize_items")]
     inside_items: Vec<Item>,
     // application: Applications,
     visibility: MyVisibility,
@@ -797,6 +1329,10 @@ impl FnItem {
     //     }
     // }
 
+    pub fn get_item(&self) -> ItemFn {
+        self.item.clone().unwrap()
+    }
+
     pub fn to_item(&self) -> Item {
         Item::Fn(self.item.clone().unwrap())
     }
@@ -804,10 +1340,224 @@ impl FnItem {
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
+
+    pub fn get_visibility(&self) -> &MyVisibility {
+        &self.visibility
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ForeignFnItem {
+    fn_name: Name,
+    #[serde(serialize_with = "serialize_item")]
+    item: Option<ForeignItemFn>,
+    visibility: MyVisibility,
+}
+
+impl ForeignFnItem {
+    pub fn new() -> Self {
+        ForeignFnItem {
+            fn_name: Name::none(),
+            item: None,
+            visibility: MyVisibility::Pri,
+        }
+    }
+
+    pub fn insert_fn_name(&mut self, fn_name: &String) {
+        self.fn_name = Name::new(fn_name);
+    }
+
+    pub fn insert_item(&mut self, item: &ForeignItemFn) {
+        self.item = Some(item.clone());
+    }
+
+    pub fn insert_parent_mod_tree(&mut self, mod_tree: &String) {
+        self.fn_name
+            .insert_parent_mod_tree_for_fn_struct_enum_union_trait(mod_tree);
+    }
+
+    pub fn get_name(&self) -> String {
+        self.fn_name.get_name()
+    }
+
+    pub fn get_complete_name(&self) -> String {
+        self.fn_name.get_import_name().to_string()
+    }
+
+    pub fn get_item(&self) -> ForeignItemFn {
+        self.item.clone().unwrap()
+    }
+
+    pub fn insert_visibility(&mut self, visibility: MyVisibility) {
+        self.visibility = visibility;
+    }
+
+    pub fn get_visibility(&self) -> &MyVisibility {
+        &self.visibility
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ForeignStaticItem {
+    static_name: Name,
+    #[serde(serialize_with = "serialize_item")]
+    item: Option<ForeignItemStatic>,
+    visibility: MyVisibility,
+}
+
+impl ForeignStaticItem {
+    pub fn new() -> Self {
+        ForeignStaticItem {
+            static_name: Name::none(),
+            item: None,
+            visibility: MyVisibility::Pri,
+        }
+    }
+
+    pub fn insert_static_name(&mut self, static_name: &String) {
+        self.static_name = Name::new(static_name);
+    }
+
+    pub fn insert_item(&mut self, item: &ForeignItemStatic) {
+        self.item = Some(item.clone());
+    }
+
+    pub fn insert_parent_mod_tree(&mut self, mod_tree: &String) {
+        self.static_name
+            .insert_parent_mod_tree_for_fn_struct_enum_union_trait(mod_tree);
+    }
+
+    pub fn get_name(&self) -> String {
+        self.static_name.get_name()
+    }
+
+    pub fn get_complete_name(&self) -> String {
+        self.static_name.get_import_name().to_string()
+    }
+
+    pub fn get_item(&self) -> ForeignItemStatic {
+        self.item.clone().unwrap()
+    }
+
+    pub fn insert_visibility(&mut self, visibility: MyVisibility) {
+        self.visibility = visibility;
+    }
+
+    pub fn get_visibility(&self) -> &MyVisibility {
+        &self.visibility
+    }
+}
+
+// an `extern` block, its fns and statics are declared in a native library
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ForeignModItem {
+    mod_tree: MyPath,
+    #[serde(serialize_with = "serialize_item")]
+    item: Option<ItemForeignMod>,
+    fns: Vec<ForeignFnItem>,
+    statics: Vec<ForeignStaticItem>,
+}
+
+impl ForeignModItem {
+    pub fn new() -> Self {
+        ForeignModItem {
+            mod_tree: MyPath::none(),
+            item: None,
+            fns: Vec::new(),
+            statics: Vec::new(),
+        }
+    }
+
+    pub fn insert_item(&mut self, item: &ItemForeignMod) {
+        self.item = Some(item.clone());
+    }
+
+    pub fn get_item(&self) -> &ItemForeignMod {
+        self.item.as_ref().unwrap()
+    }
+
+    pub fn insert_function(&mut self, item: &ForeignFnItem) {
+        self.fns.push(item.clone());
+    }
+
+    pub fn insert_static(&mut self, item: &ForeignStaticItem) {
+        self.statics.push(item.clone());
+    }
+
+    pub fn get_fns(&self) -> &Vec<ForeignFnItem> {
+        &self.fns
+    }
+
+    pub fn get_statics(&self) -> &Vec<ForeignStaticItem> {
+        &self.statics
+    }
+
+    pub fn insert_parent_mod_tree(&mut self, mod_tree: &String) {
+        self.mod_tree = MyPath::new(mod_tree);
+        for function in self.fns.iter_mut() {
+            function.insert_parent_mod_tree(mod_tree);
+        }
+        for static_item in self.statics.iter_mut() {
+            static_item.insert_parent_mod_tree(mod_tree);
+        }
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
+
+    // `extern { ... }` without an abi is `extern "C"`
+    pub fn get_abi(&self) -> String {
+        match &self.get_item().abi.name {
+            Some(name) => name.value(),
+            None => "C".to_string(),
+        }
+    }
+
+    // the names of the native libraries in `#[link(name = "...")]`
+    pub fn get_links(&self) -> Vec<String> {
+        let mut links: Vec<String> = Vec::new();
+        for attr in self.get_item().attrs.iter() {
+            if !attr.path().is_ident("link") {
+                continue;
+            }
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    links.push(name.value());
+                } else if meta.input.peek(Token![=]) {
+                    let _: Lit = meta.value()?.parse()?;
+                }
+                Ok(())
+            });
+        }
+        links
+    }
+
+    pub fn clear(&mut self) {
+        self.fns.clear();
+        self.statics.clear();
+    }
+
+    pub fn to_item(&self) -> Item {
+        let mut item_foreign_mod = self.item.clone().unwrap();
+        for static_item in self.statics.iter() {
+            item_foreign_mod
+                .items
+                .push(ForeignItem::Static(static_item.get_item()));
+        }
+        for function in self.fns.iter() {
+            item_foreign_mod
+                .items
+                .push(ForeignItem::Fn(function.get_item()));
+        }
+        Item::ForeignMod(item_foreign_mod)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ImplTypeItem {
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ImplItemType>,
     visibility: MyVisibility,
 }
@@ -828,13 +1578,18 @@ impl ImplTypeItem {
         self.item.clone().unwrap()
     }
 
+    pub fn get_name(&self) -> String {
+        self.item.as_ref().unwrap().ident.to_string()
+    }
+
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ImplConstItem {
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ImplItemConst>,
     visibility: MyVisibility,
 }
@@ -855,17 +1610,23 @@ impl ImplConstItem {
         self.item.clone().unwrap()
     }
 
+    pub fn get_name(&self) -> String {
+        self.item.as_ref().unwrap().ident.to_string()
+    }
+
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ImplFnItem {
     fn_name: Name,
     complete_name_in_file: String,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ImplItemFn>,
     // has_items: bool,
+    #[serde(serialize_with = "serialize_items")]
     inside_items: Vec<Item>,
     visibility: MyVisibility,
 }
@@ -921,30 +1682,55 @@ impl ImplFnItem {
         self.item.clone().unwrap()
     }
 
+    pub fn clear_stmts(&mut self) {
+        if let Some(item) = &mut self.item {
+            item.block.stmts = get_cleared_block().stmts;
+        }
+    }
+
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
 
+    pub fn get_visibility(&self) -> &MyVisibility {
+        &self.visibility
+    }
+
     pub fn change_name(
         &mut self,
         mod_context: &Rc<RefCell<ModContext>>,
         struct_name: &Name,
+        self_type: &SelfType,
         trait_name: &Option<Name>,
     ) {
-        if let None = trait_name {
+        if trait_name.is_none() && !self_type.is_path() {
+            let mut fn_path = mod_context.borrow().get_mod_tree();
+            fn_path.down(&format!("<impl {}>", self_type.get_type_string()));
+            fn_path.down(&self.fn_name.get_name());
+            let complete_name = fn_path.to_string();
+            self.fn_name.insert_complete_name(&complete_name);
+            self.fn_name.insert_import_name(&complete_name);
+        } else if let None = trait_name {
             let mod_path = mod_context.borrow().get_mod_tree();
             let struct_path = struct_name.get_import_name();
             let mut up_struct_path = struct_path.clone();
             up_struct_path.up();
+            let concrete_args = self_type.get_concrete_args_string();
             if mod_path == up_struct_path {
                 let mut fn_path = struct_path.clone();
+                if let Some(concrete_args) = &concrete_args {
+                    fn_path.down(concrete_args);
+                }
                 fn_path.down(&self.fn_name.get_name());
                 let complete_name = fn_path.to_string();
                 self.fn_name.insert_complete_name(&complete_name);
                 self.fn_name.insert_import_name(&complete_name);
             } else {
                 let mut fn_path = mod_path.clone();
-                let struct_path_string = struct_path.to_string();
+                let mut struct_path_string = struct_path.to_string();
+                if let Some(concrete_args) = &concrete_args {
+                    struct_path_string += concrete_args;
+                }
                 fn_path.down(&format!("<impl {}>", struct_path_string));
                 fn_path.down(&self.fn_name.get_name());
                 let complete_name = fn_path.to_string();
@@ -952,13 +1738,15 @@ impl ImplFnItem {
                 self.fn_name.insert_import_name(&complete_name);
             }
         } else {
-            let struct_path = struct_name.get_import_name();
+            let mut struct_path_string = struct_name.get_import_name().to_string();
+            if !self_type.is_path() || struct_path_string.eq("") {
+                struct_path_string = self_type.get_type_string();
+            } else if let Some(concrete_args) = self_type.get_concrete_args_string() {
+                struct_path_string += &concrete_args;
+            }
             let trait_path = trait_name.as_ref().unwrap().get_import_name();
-            let fn_path_string = format!(
-                "<{} as {}>",
-                struct_path.to_string(),
-                trait_path.to_string()
-            ) + "::"
+            let fn_path_string = format!("<{} as {}>", struct_path_string, trait_path.to_string())
+                + "::"
                 + &self.fn_name.get_name();
             self.fn_name.insert_complete_name(&fn_path_string);
             self.fn_name.insert_import_name(&fn_path_string);
@@ -974,11 +1762,14 @@ impl ImplFnItem {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ImplItem {
     impl_num: i32,
+    mod_tree: MyPath,
     struct_name: Name,
+    self_type: SelfType,
     trait_name: Option<Name>,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemImpl>,
     types: Vec<ImplTypeItem>,
     consts: Vec<ImplConstItem>,
@@ -990,7 +1781,9 @@ impl ImplItem {
     pub fn new() -> Self {
         ImplItem {
             impl_num: 0,
+            mod_tree: MyPath::none(),
             struct_name: Name::none(),
+            self_type: SelfType::Other(String::new()),
             trait_name: None,
             item: None,
             types: Vec::new(),
@@ -1016,6 +1809,18 @@ impl ImplItem {
         self.struct_name = name.clone();
     }
 
+    pub fn insert_self_type(&mut self, self_type: SelfType) {
+        self.self_type = self_type;
+    }
+
+    pub fn get_self_type(&self) -> &SelfType {
+        &self.self_type
+    }
+
+    pub fn change_self_type_names(&mut self, mod_context: &Rc<RefCell<ModContext>>) {
+        self.self_type.change_names(mod_context);
+    }
+
     pub fn insert_trait_name(&mut self, trait_name: &String) {
         self.trait_name = Some(Name::new(trait_name));
     }
@@ -1051,6 +1856,45 @@ impl ImplItem {
         return self.impl_num;
     }
 
+    pub fn insert_mod_tree(&mut self, mod_tree: &MyPath) {
+        self.mod_tree = mod_tree.clone();
+    }
+
+    pub fn get_mod_tree(&self) -> &MyPath {
+        &self.mod_tree
+    }
+
+    pub fn get_types(&self) -> &Vec<ImplTypeItem> {
+        &self.types
+    }
+
+    pub fn get_consts(&self) -> &Vec<ImplConstItem> {
+        &self.consts
+    }
+
+    pub fn clear_fn_stmts(&mut self) {
+        for function in self.functions.iter_mut() {
+            function.clear_stmts();
+        }
+    }
+
+    // the associated types and consts stay, they are picked once the fns are known
+    pub fn clear_fns(&mut self) {
+        self.functions.clear();
+    }
+
+    pub fn retain_fns(&mut self, names: &[String]) {
+        self.functions
+            .retain(|impl_fn_item| names.contains(&impl_fn_item.get_name()));
+    }
+
+    pub fn retain_types_and_consts(&mut self, names: &[String]) {
+        self.types
+            .retain(|impl_type_item| names.contains(&impl_type_item.get_name()));
+        self.consts
+            .retain(|impl_const_item| names.contains(&impl_const_item.get_name()));
+    }
+
     pub fn get_item(&self) -> &ItemImpl {
         self.item.as_ref().unwrap()
     }
@@ -1099,7 +1943,12 @@ impl ImplItem {
 
     pub fn change_function_name(&mut self, mod_context: &Rc<RefCell<ModContext>>) {
         for function in self.functions.iter_mut() {
-            function.change_name(mod_context, &self.struct_name, &self.trait_name);
+            function.change_name(
+                mod_context,
+                &self.struct_name,
+                &self.self_type,
+                &self.trait_name,
+            );
         }
     }
 
@@ -1134,9 +1983,10 @@ impl ImplItem {
     // }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct StructItem {
     struct_name: Name,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemStruct>,
     // applications: Applications,
     visibility: MyVisibility,
@@ -1208,9 +2058,10 @@ impl StructItem {
     // }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct EnumItem {
     enum_name: Name,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemEnum>,
     // applications: Applications,
     visibility: MyVisibility,
@@ -1282,9 +2133,10 @@ impl EnumItem {
     // }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct UnionItem {
     union_name: Name,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemUnion>,
     // applications: Applications,
     visibility: MyVisibility,
@@ -1356,8 +2208,9 @@ impl UnionItem {
     // }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TraitTypeItem {
+    #[serde(serialize_with = "serialize_item")]
     item: Option<TraitItemType>,
 }
 
@@ -1373,10 +2226,15 @@ impl TraitTypeItem {
     pub fn get_item(&self) -> TraitItemType {
         self.item.clone().unwrap()
     }
+
+    pub fn get_name(&self) -> String {
+        self.item.as_ref().unwrap().ident.to_string()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TraitConstItem {
+    #[serde(serialize_with = "serialize_item")]
     item: Option<TraitItemConst>,
 }
 
@@ -1392,14 +2250,20 @@ impl TraitConstItem {
     pub fn get_item(&self) -> TraitItemConst {
         self.item.clone().unwrap()
     }
+
+    pub fn get_name(&self) -> String {
+        self.item.as_ref().unwrap().ident.to_string()
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct TraitFnItem {
     fn_name: Name,
     complete_name_in_file: String,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<TraitItemFn>,
     // has_items: bool,
+    #[serde(serialize_with = "serialize_items")]
     inside_items: Vec<Item>,
 }
 
@@ -1472,13 +2336,15 @@ impl TraitFnItem {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TraitItem {
     trait_name: Name,
+    #[serde(serialize_with = "serialize_item")]
     item: Option<ItemTrait>,
     types: Vec<TraitTypeItem>,
     consts: Vec<TraitConstItem>,
     functions: Vec<TraitFnItem>,
+    supertraits: Vec<Name>,
     // applications: Applications,
     visibility: MyVisibility,
 }
@@ -1491,6 +2357,7 @@ impl TraitItem {
             types: Vec::new(),
             consts: Vec::new(),
             functions: Vec::new(),
+            supertraits: Vec::new(),
             // applications: Applications::new(),
             visibility: MyVisibility::Pri,
         }
@@ -1558,10 +2425,52 @@ impl TraitItem {
         &self.functions
     }
 
+    pub fn get_types(&self) -> &Vec<TraitTypeItem> {
+        &self.types
+    }
+
+    pub fn get_consts(&self) -> &Vec<TraitConstItem> {
+        &self.consts
+    }
+
+    pub fn has_function(&self, name: &String) -> bool {
+        self.functions
+            .iter()
+            .any(|trait_fn_item| trait_fn_item.get_name().eq(name))
+    }
+
+    pub fn has_type_or_const(&self, name: &String) -> bool {
+        self.types
+            .iter()
+            .any(|trait_type_item| trait_type_item.get_name().eq(name))
+            || self
+                .consts
+                .iter()
+                .any(|trait_const_item| trait_const_item.get_name().eq(name))
+    }
+
+    pub fn insert_supertrait(&mut self, supertrait: &Name) {
+        self.supertraits.push(supertrait.clone());
+    }
+
+    pub fn get_supertraits(&self) -> &Vec<Name> {
+        &self.supertraits
+    }
+
+    pub fn change_supertrait_names(&mut self, mod_context: &Rc<RefCell<ModContext>>) {
+        for supertrait in self.supertraits.iter_mut() {
+            supertrait.change_name_for_impl_trait_name(mod_context);
+        }
+    }
+
     pub fn insert_visibility(&mut self, visibility: MyVisibility) {
         self.visibility = visibility;
     }
 
+    pub fn get_visibility(&self) -> &MyVisibility {
+        &self.visibility
+    }
+
     pub fn insert_parent_mod_tree(&mut self, mod_tree: &String) {
         self.trait_name
             .insert_parent_mod_tree_for_fn_struct_enum_union_trait(mod_tree);
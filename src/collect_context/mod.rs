@@ -1,5 +1,11 @@
+pub mod config;
+mod coverage;
 pub mod crate_context;
+mod doctest;
 mod items_context;
 mod mod_context;
 pub mod result;
+mod syntax_calls;
 mod syntax_context;
+mod test_scaffold;
+mod verbatim;
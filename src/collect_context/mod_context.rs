@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::BTreeMap,
     fmt,
     fs::{self, read_to_string},
     path::PathBuf,
@@ -8,11 +8,11 @@ use std::{
     rc::Rc,
 };
 
-use syn::{parse_file, token::Else, Item};
+use syn::{parse_file, spanned::Spanned, token::Else, Item};
 
 use super::{
     crate_context::{self, CrateContext},
-    items_context::{MyPath, MyVisibility, Name, UseTree},
+    items_context::{ImplItem, MacroItem, MyPath, MyVisibility, Name, UseTree},
     result::{FnData, StructData},
     syntax_context::SyntaxContext,
 };
@@ -25,6 +25,7 @@ pub struct ModModInfo {
     parent_directory_path: PathBuf,
     mod_file_directory_path: Option<PathBuf>,
     visibility: MyVisibility,
+    span_file: String,
 }
 
 impl ModModInfo {
@@ -36,6 +37,7 @@ impl ModModInfo {
             parent_directory_path: PathBuf::new(),
             mod_file_directory_path: None,
             visibility: MyVisibility::Pri,
+            span_file: String::new(),
         }
     }
 
@@ -73,6 +75,10 @@ impl ModModInfo {
         self.visibility = visibility;
     }
 
+    pub fn insert_span_file(&mut self, span_file: &str) {
+        self.span_file = span_file.to_string();
+    }
+
     pub fn get_mod_name(&self) -> String {
         return self.mod_name.clone();
     }
@@ -129,6 +135,14 @@ impl FunctionModInfo {
     }
 }
 
+// proc-macro2 names the spans of every parsed file like `<parsed string 3>`
+pub fn get_span_file(syntax: &syn::File) -> String {
+    match syntax.items.first() {
+        Some(item) => item.span().file(),
+        None => String::new(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ModInfo {
     Mod(ModModInfo),
@@ -280,6 +294,7 @@ impl ModContext {
                     mod_mod_info.insert_mod_file_directory_path(&mod_directory_path);
                     let code = read_to_string(&mod_file_path).unwrap();
                     let syntax = parse_file(&code).unwrap();
+                    mod_mod_info.insert_span_file(&get_span_file(&syntax));
                     let mod_info = ModInfo::Mod(mod_mod_info);
                     let sub_mod = ModContext::new();
                     sub_mod.borrow_mut().insert_mod_info(&mod_info);
@@ -294,6 +309,7 @@ impl ModContext {
                         mod_mod_info.insert_mod_file_directory_path(&mod_directory_path);
                         let code = read_to_string(&single_file_path).unwrap();
                         let syntax = parse_file(&code).unwrap();
+                        mod_mod_info.insert_span_file(&get_span_file(&syntax));
                         let mod_info = ModInfo::Mod(mod_mod_info);
                         let sub_mod = ModContext::new();
                         sub_mod.borrow_mut().insert_mod_info(&mod_info);
@@ -306,6 +322,7 @@ impl ModContext {
                         mod_mod_info.insert_file_path(&single_file_path);
                         let code = read_to_string(&single_file_path).unwrap();
                         let syntax = parse_file(&code).unwrap();
+                        mod_mod_info.insert_span_file(&get_span_file(&syntax));
                         let mod_info = ModInfo::Mod(mod_mod_info);
                         let sub_mod = ModContext::new();
                         sub_mod.borrow_mut().insert_mod_info(&mod_info);
@@ -344,6 +361,7 @@ impl ModContext {
                         mod_mod_info.insert_mod_file_directory_path(&mod_directory_path);
                         let code = read_to_string(&mod_file_path).unwrap();
                         let syntax = parse_file(&code).unwrap();
+                        mod_mod_info.insert_span_file(&get_span_file(&syntax));
                         let mod_info = ModInfo::Mod(mod_mod_info);
                         let sub_mod = ModContext::new();
                         sub_mod.borrow_mut().insert_mod_info(&mod_info);
@@ -379,7 +397,8 @@ impl ModContext {
                             mod_mod_info.insert_mod_file_directory_path(&mod_directory_path);
                             let code = read_to_string(&mod_file_path).unwrap();
                             let syntax = parse_file(&code).unwrap();
-                            let mod_info = ModInfo::new();
+                            mod_mod_info.insert_span_file(&get_span_file(&syntax));
+                            let mod_info = ModInfo::Mod(mod_mod_info);
                             let sub_mod = ModContext::new();
                             sub_mod.borrow_mut().insert_mod_info(&mod_info);
                             ModContext::parse_from_items(&sub_mod, &syntax.items, crate_mod);
@@ -391,7 +410,8 @@ impl ModContext {
                             mod_mod_info.insert_file_path(&mod_file_path);
                             let code = read_to_string(&mod_file_path).unwrap();
                             let syntax = parse_file(&code).unwrap();
-                            let mod_info = ModInfo::new();
+                            mod_mod_info.insert_span_file(&get_span_file(&syntax));
+                            let mod_info = ModInfo::Mod(mod_mod_info);
                             let sub_mod = ModContext::new();
                             sub_mod.borrow_mut().insert_mod_info(&mod_info);
                             ModContext::parse_from_items(&sub_mod, &syntax.items, crate_mod);
@@ -413,6 +433,52 @@ impl ModContext {
         self.mod_info = mod_info.clone();
     }
 
+    pub fn get_source_files(&self, source_files: &mut BTreeMap<String, PathBuf>) {
+        if let ModInfo::Mod(mod_mod_info) = &self.mod_info {
+            if !mod_mod_info.span_file.is_empty() {
+                source_files.insert(
+                    mod_mod_info.span_file.clone(),
+                    mod_mod_info.file_path.clone(),
+                );
+            }
+        }
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod.borrow().get_source_files(source_files);
+        }
+    }
+
+    pub fn get_mod_visibilities(&self, mod_visibilities: &mut BTreeMap<String, MyVisibility>) {
+        if let ModInfo::Mod(mod_mod_info) = &self.mod_info {
+            mod_visibilities.insert(
+                mod_mod_info.get_mod_tree().to_string(),
+                mod_mod_info.visibility.clone(),
+            );
+        }
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod.borrow().get_mod_visibilities(mod_visibilities);
+        }
+    }
+
+    // the `pub use` trees of every mod, with the mod tree they are declared in
+    pub fn get_pub_uses(&self, pub_uses: &mut Vec<(String, UseTree)>) {
+        if let ModInfo::Mod(mod_mod_info) = &self.mod_info {
+            let mod_tree = mod_mod_info.get_mod_tree().to_string();
+            for use_tree in self.get_pub_use() {
+                pub_uses.push((mod_tree.clone(), use_tree));
+            }
+        }
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod.borrow().get_pub_uses(pub_uses);
+        }
+    }
+
+    pub fn get_macro_items(&self, macro_items: &mut Vec<MacroItem>) {
+        self.syntax_context.get_macro_items(macro_items);
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod.borrow().get_macro_items(macro_items);
+        }
+    }
+
     pub fn get_all_mod_trees(&self, mod_trees: &mut Vec<String>) {
         mod_trees.push(self.mod_info.get_mod_tree().to_string());
         for sub_mod in self.sub_mods.iter() {
@@ -551,22 +617,20 @@ impl ModContext {
 
     pub fn get_result(
         &self,
-        fns: &mut HashMap<String, FnData>,
-        structs: &mut HashMap<String, StructData>,
+        fns: &mut BTreeMap<String, FnData>,
+        structs: &mut BTreeMap<String, StructData>,
     ) {
-        self.syntax_context.get_result(fns, structs);
+        self.syntax_context
+            .get_result(&self.mod_info.get_mod_tree().to_string(), fns, structs);
         for sub_mod in self.sub_mods.iter() {
             sub_mod.borrow().get_result(fns, structs);
         }
     }
 
-    pub fn get_relative_types_for_struct(&self, name: &String, relative_types: &mut Vec<String>) {
-        self.syntax_context
-            .get_relative_types_for_struct(name, relative_types);
+    pub fn get_impls_for_struct(&self, name: &String, impls: &mut Vec<ImplItem>) {
+        self.syntax_context.get_impls_for_struct(name, impls);
         for sub_mod in self.sub_mods.iter() {
-            sub_mod
-                .borrow()
-                .get_relative_types_for_struct(name, relative_types);
+            sub_mod.borrow().get_impls_for_struct(name, impls);
         }
     }
 
@@ -588,12 +652,66 @@ impl ModContext {
     //     }
     // }
 
+    pub fn get_constructors(
+        &self,
+        crate_name: &str,
+        structs: &BTreeMap<String, StructData>,
+        constructors: &mut BTreeMap<String, Vec<(usize, usize, String)>>,
+    ) {
+        self.syntax_context.get_constructors(
+            &self.mod_info.get_mod_tree().to_string(),
+            crate_name,
+            structs,
+            constructors,
+        );
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod
+                .borrow()
+                .get_constructors(crate_name, structs, constructors);
+        }
+    }
+
+    pub fn get_fn_output_names(&self, fn_output_names: &mut BTreeMap<String, String>) {
+        self.syntax_context
+            .get_fn_output_names(&self.mod_info.get_mod_tree().to_string(), fn_output_names);
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod.borrow().get_fn_output_names(fn_output_names);
+        }
+    }
+
+    pub fn write_syntax_calls(
+        &self,
+        output_path: &PathBuf,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
+        fn_output_names: &BTreeMap<String, String>,
+        crate_context: &CrateContext,
+    ) {
+        self.syntax_context.write_syntax_calls(
+            output_path,
+            &self.mod_info.get_mod_tree().to_string(),
+            fns,
+            structs,
+            fn_output_names,
+            crate_context,
+        );
+        for sub_mod in self.sub_mods.iter() {
+            sub_mod.borrow().write_syntax_calls(
+                output_path,
+                fns,
+                structs,
+                fn_output_names,
+                crate_context,
+            );
+        }
+    }
+
     pub fn get_all_context(
         &self,
         output_path: &PathBuf,
         mod_trees: &Vec<String>,
-        fns: &HashMap<String, FnData>,
-        structs: &HashMap<String, StructData>,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
         crate_context: &CrateContext,
     ) {
         self.syntax_context.get_context(
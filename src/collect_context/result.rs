@@ -1,36 +1,185 @@
 use std::collections::HashSet;
 
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use serde::Serialize;
+use syn::{Attribute, Signature};
+
 use super::items_context::{
-    EnumItem, FnItem, ImplFnItem, ImplItem, StructItem, TraitFnItem, TraitItem, UnionItem,
+    ConstItem, EnumItem, FnItem, ForeignFnItem, ForeignModItem, ForeignStaticItem, ImplFnItem,
+    ImplItem, MacroItem, MyVisibility, SourceLocation, StaticItem, StructItem, TraitFnItem,
+    TraitItem, UnionItem,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum FnType {
     Fn(FnItem),
     ImplFn(ImplFnItem, ImplItem),
     TraitFn(TraitFnItem, TraitItem),
+    ForeignFn(ForeignFnItem, ForeignModItem),
+}
+
+impl FnType {
+    pub fn get_complete_name(&self) -> String {
+        match self {
+            FnType::Fn(fn_item) => fn_item.get_complete_name(),
+            FnType::ImplFn(impl_fn_item, _) => impl_fn_item.get_complete_name(),
+            FnType::TraitFn(trait_fn_item, _) => trait_fn_item.get_complete_name(),
+            FnType::ForeignFn(foreign_fn_item, _) => foreign_fn_item.get_complete_name(),
+        }
+    }
+
+    pub fn get_kind(&self) -> String {
+        match self {
+            FnType::Fn(_) => "fn".to_string(),
+            FnType::ImplFn(_, impl_item) => match impl_item.get_trait_name() {
+                Some(_) => "trait_impl_fn".to_string(),
+                None => "impl_fn".to_string(),
+            },
+            FnType::TraitFn(_, _) => "trait_fn".to_string(),
+            FnType::ForeignFn(_, _) => "foreign_fn".to_string(),
+        }
+    }
+
+    pub fn get_visibility(&self) -> MyVisibility {
+        match self {
+            FnType::Fn(fn_item) => fn_item.get_visibility().clone(),
+            FnType::ImplFn(impl_fn_item, _) => impl_fn_item.get_visibility().clone(),
+            FnType::TraitFn(_, trait_item) => trait_item.get_visibility().clone(),
+            FnType::ForeignFn(foreign_fn_item, _) => foreign_fn_item.get_visibility().clone(),
+        }
+    }
+
+    pub fn get_signature(&self) -> Signature {
+        match self {
+            FnType::Fn(fn_item) => fn_item.get_item().sig,
+            FnType::ImplFn(impl_fn_item, _) => impl_fn_item.get_item().sig,
+            FnType::TraitFn(trait_fn_item, _) => trait_fn_item.get_item().sig,
+            FnType::ForeignFn(foreign_fn_item, _) => foreign_fn_item.get_item().sig,
+        }
+    }
+
+    pub fn get_tokens(&self) -> TokenStream {
+        match self {
+            FnType::Fn(fn_item) => fn_item.get_item().to_token_stream(),
+            FnType::ImplFn(impl_fn_item, _) => impl_fn_item.get_item().to_token_stream(),
+            FnType::TraitFn(trait_fn_item, _) => trait_fn_item.get_item().to_token_stream(),
+            FnType::ForeignFn(foreign_fn_item, _) => foreign_fn_item.get_item().to_token_stream(),
+        }
+    }
+
+    pub fn get_attrs(&self) -> Vec<Attribute> {
+        match self {
+            FnType::Fn(fn_item) => fn_item.get_item().attrs,
+            FnType::ImplFn(impl_fn_item, _) => impl_fn_item.get_item().attrs,
+            FnType::TraitFn(trait_fn_item, _) => trait_fn_item.get_item().attrs,
+            FnType::ForeignFn(foreign_fn_item, _) => foreign_fn_item.get_item().attrs,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FnData {
     pub fn_name: String,
     pub complete_fn_name: String,
     pub fn_type: FnType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 
 pub enum StructType {
     Struct(StructItem),
     Enum(EnumItem),
     Union(UnionItem),
     Trait(TraitItem),
+    Static(StaticItem),
+    Const(ConstItem),
+    ForeignStatic(ForeignStaticItem, ForeignModItem),
+    // an item expanded from a macro invocation, keyed by the name of the expanded item
+    Macro(MacroItem),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 
 pub struct StructData {
     pub struct_name: String,
     pub complete_struct_name: String,
     pub struct_type: StructType,
 }
+
+// one line of `rfocxt/index.jsonl`
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexRecord {
+    pub complete_name: String,
+    pub kind: String,
+    pub mod_path: String,
+    pub visibility: String,
+    pub signature: String,
+    pub doc: String,
+    pub context_file: String,
+    pub location: Option<SourceLocation>,
+    // the context declares foreign items, tests must link the native libraries
+    pub uses_ffi: bool,
+    pub native_libs: Vec<String>,
+}
+
+// the files of one function in `rfocxt/manifest.json`, relative to `rfocxt`
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestEntry {
+    pub context: String,
+    pub test_scaffold: Option<String>,
+    pub callsandtypes: String,
+    pub new_callsandtypes: String,
+    // only call_chain writes these, a `--syntax-only` run leaves them out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub basic_blocks: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branches: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panics: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collect_context::crate_context::CrateContext;
+    use std::{collections::BTreeMap, env::temp_dir, fs};
+
+    #[test]
+    fn fns_and_structs_are_serialized_with_their_source() {
+        let crate_path = temp_dir().join("rfocxt_result");
+        let _ = fs::remove_dir_all(&crate_path);
+        fs::create_dir_all(crate_path.join("src")).unwrap();
+        fs::write(
+            crate_path.join("Cargo.toml"),
+            "[package]\nname = \"sample\"\n",
+        )
+        .unwrap();
+        fs::write(
+            crate_path.join("src/lib.rs"),
+            "pub struct Meters(pub f64);\n\npub(crate) fn double(x: i32) -> i32 {\n    x * 2\n}\n",
+        )
+        .unwrap();
+        let mut crate_context = CrateContext::new(&crate_path);
+        crate_context.parse_crate();
+        crate_context.change_all_names();
+        let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+        let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
+        crate_context.get_result(&mut fns, &mut structs);
+        let fns = serde_json::to_value(&fns).unwrap();
+        let structs = serde_json::to_value(&structs).unwrap();
+        let fn_item = &fns["sample::double"]["fn_type"]["Fn"];
+        assert_eq!(fn_item["visibility"], "pub(crate)");
+        assert_eq!(fn_item["fn_name"]["complete_name"], "sample::double");
+        assert_eq!(
+            fn_item["item"]["source"],
+            "pub(crate) fn double(x: i32) -> i32 {\n    x * 2\n}"
+        );
+        assert_eq!(fn_item["item"]["start_line"], 3);
+        assert_eq!(fn_item["item"]["end_line"], 5);
+        let struct_item = &structs["sample::Meters"]["struct_type"]["Struct"];
+        assert_eq!(struct_item["visibility"], "pub");
+        assert_eq!(struct_item["item"]["source"], "pub struct Meters(pub f64);");
+        assert_eq!(struct_item["relative_types"], serde_json::json!(["f64"]));
+    }
+}
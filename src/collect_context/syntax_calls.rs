@@ -0,0 +1,463 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use call_chain::analysis::exporter::{CallsAndTypes, ImplCall};
+use syn::{
+    parse::Parser,
+    punctuated::Punctuated,
+    visit::{self, Visit},
+    Block, Expr, ExprMethodCall, ExprPath, ExprStruct, ExprUnsafe, FnArg, Macro, PatTupleStruct,
+    Path, ReturnType, Signature, Token, TypePath,
+};
+
+use super::{
+    items_context::UseTree,
+    result::{FnData, FnType, StructData, StructType},
+};
+
+// where a fn is written, the paths in its body are resolved from there
+pub struct FnScope<'a> {
+    pub crate_name: &'a str,
+    pub mod_tree: &'a str,
+    pub use_trees: &'a [UseTree],
+    pub self_type: Option<String>,
+    // the types named in the self type of the impl, `Circle` of `[Circle; 2]`
+    pub self_type_names: Vec<String>,
+    pub trait_name: Option<String>,
+}
+
+// the paths, method names and types written in a fn, without the MIR these are all
+// `--syntax-only` knows about its calls
+struct BodyVisitor {
+    expr_paths: Vec<Vec<String>>,
+    methods: Vec<String>,
+    type_paths: Vec<Vec<String>>,
+    unsafe_blocks: Vec<ExprUnsafe>,
+}
+
+impl BodyVisitor {
+    fn new() -> Self {
+        BodyVisitor {
+            expr_paths: Vec::new(),
+            methods: Vec::new(),
+            type_paths: Vec::new(),
+            unsafe_blocks: Vec::new(),
+        }
+    }
+}
+
+fn get_segments(path: &Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect()
+}
+
+impl<'ast> Visit<'ast> for BodyVisitor {
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        if node.qself.is_none() {
+            self.expr_paths.push(get_segments(&node.path));
+        }
+        visit::visit_expr_path(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.methods.push(node.method.to_string());
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_struct(&mut self, node: &'ast ExprStruct) {
+        if node.qself.is_none() {
+            self.type_paths.push(get_segments(&node.path));
+        }
+        visit::visit_expr_struct(self, node);
+    }
+
+    fn visit_pat_tuple_struct(&mut self, node: &'ast PatTupleStruct) {
+        if node.qself.is_none() {
+            self.expr_paths.push(get_segments(&node.path));
+        }
+        visit::visit_pat_tuple_struct(self, node);
+    }
+
+    fn visit_type_path(&mut self, node: &'ast TypePath) {
+        if node.qself.is_none() {
+            self.type_paths.push(get_segments(&node.path));
+        }
+        visit::visit_type_path(self, node);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
+        self.unsafe_blocks.push(node.clone());
+        visit::visit_expr_unsafe(self, node);
+    }
+
+    // the args of `println!`, `assert_eq!`, `vec!` and the like are mostly exprs
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        let parser = Punctuated::<Expr, Token![,]>::parse_terminated;
+        if let Ok(exprs) = parser.parse2(node.tokens.clone()) {
+            for expr in exprs.iter() {
+                self.visit_expr(expr);
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+}
+
+fn get_parent_mod_tree(mod_tree: &str) -> &str {
+    match mod_tree.rfind("::") {
+        Some(index) => &mod_tree[..index],
+        None => mod_tree,
+    }
+}
+
+// the complete paths a path written in the scope can stand for
+pub fn resolve_path(segments: &[String], scope: &FnScope) -> Vec<String> {
+    let mut resolved_paths: Vec<String> = Vec::new();
+    if segments.is_empty() {
+        return resolved_paths;
+    }
+    let rest = &segments[1..];
+    let join = |prefix: &str, rest: &[String]| {
+        let mut path = prefix.to_string();
+        for segment in rest.iter() {
+            path += "::";
+            path += segment;
+        }
+        path
+    };
+    match segments[0].as_str() {
+        "crate" => resolved_paths.push(join(scope.crate_name, rest)),
+        "self" => resolved_paths.push(join(scope.mod_tree, rest)),
+        "super" => {
+            let mut mod_tree = scope.mod_tree;
+            let mut rest = segments;
+            while !rest.is_empty() && rest[0].eq("super") {
+                mod_tree = get_parent_mod_tree(mod_tree);
+                rest = &rest[1..];
+            }
+            resolved_paths.push(join(mod_tree, rest));
+        }
+        "Self" => {
+            if let Some(self_type) = &scope.self_type {
+                resolved_paths.push(join(self_type, rest));
+            }
+            if let Some(trait_name) = &scope.trait_name {
+                resolved_paths.push(join(trait_name, rest));
+            }
+        }
+        first => {
+            for use_tree in scope.use_trees.iter() {
+                let use_name = match use_tree.get_alias() {
+                    Some(alias) => alias,
+                    None => use_tree.get_name(),
+                };
+                if use_name.eq(first) {
+                    resolved_paths.push(join(&use_tree.get_use_tree().to_string(), rest));
+                } else if use_name.eq("*") {
+                    let use_path = use_tree.get_use_tree().to_string();
+                    let glob_path = use_path.trim_end_matches("::*");
+                    resolved_paths.push(join(glob_path, segments));
+                }
+            }
+            resolved_paths.push(join(scope.mod_tree, segments));
+            resolved_paths.push(join(first, rest));
+        }
+    }
+    resolved_paths
+}
+
+// the self type of an impl fn and the trait it implements, by their import names,
+// impls for arrays, tuples and the like have no import name
+fn get_impl_names(fn_data: &FnData) -> Option<(String, Option<String>)> {
+    match &fn_data.fn_type {
+        FnType::ImplFn(_, impl_item) => {
+            let self_type = impl_item.get_struct_name().get_import_name().to_string();
+            if self_type.is_empty() {
+                return None;
+            }
+            Some((
+                self_type,
+                impl_item
+                    .get_trait_name()
+                    .as_ref()
+                    .map(|trait_name| trait_name.get_import_name().to_string()),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn get_impl_call(self_type: &str, trait_name: &str, fn_name: &str) -> ImplCall {
+    ImplCall {
+        call: format!("<{} as {}>::{}", self_type, trait_name, fn_name),
+        callee: format!("{}::{}", trait_name, fn_name),
+        self_type: self_type.to_string(),
+        generic_args: Vec::new(),
+    }
+}
+
+// fills the calls, types, impl calls, statics and consts of `data` from the syntax of a fn,
+// method calls are paired with the types the fn names, as the receiver types are unknown
+pub fn get_calls_and_types(
+    scope: &FnScope,
+    sig: &Signature,
+    block: &Block,
+    fns: &BTreeMap<String, FnData>,
+    structs: &BTreeMap<String, StructData>,
+    data: &mut CallsAndTypes,
+) -> Vec<ExprUnsafe> {
+    let mut visitor = BodyVisitor::new();
+    for input in sig.inputs.iter() {
+        if let FnArg::Typed(pat_type) = input {
+            visitor.visit_type(&pat_type.ty);
+        }
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        visitor.visit_type(ty);
+    }
+    visitor.visit_block(block);
+
+    let mut calls: BTreeSet<String> = BTreeSet::new();
+    let mut types: BTreeSet<String> = BTreeSet::new();
+    let mut statics: BTreeSet<String> = BTreeSet::new();
+    let mut consts: BTreeSet<String> = BTreeSet::new();
+    let mut impl_calls: BTreeSet<ImplCall> = BTreeSet::new();
+    // `Type::fn` paths, the type and the fn name
+    let mut assoc_paths: Vec<(String, String)> = Vec::new();
+    if let Some(self_type) = &scope.self_type {
+        types.insert(self_type.clone());
+    }
+    for self_type_name in scope.self_type_names.iter() {
+        if structs.contains_key(self_type_name) {
+            types.insert(self_type_name.clone());
+        }
+    }
+    for type_path in visitor.type_paths.iter() {
+        for resolved_path in resolve_path(type_path, scope) {
+            if structs.contains_key(&resolved_path) {
+                types.insert(resolved_path);
+            }
+        }
+    }
+    for expr_path in visitor.expr_paths.iter() {
+        for resolved_path in resolve_path(expr_path, scope) {
+            if fns.contains_key(&resolved_path) {
+                calls.insert(resolved_path.clone());
+            }
+            if let Some(struct_data) = structs.get(&resolved_path) {
+                match &struct_data.struct_type {
+                    StructType::Static(_) | StructType::ForeignStatic(_, _) => {
+                        statics.insert(resolved_path.clone());
+                    }
+                    StructType::Const(_) => {
+                        consts.insert(resolved_path.clone());
+                    }
+                    // an item expanded from a macro may be a fn or a global, as a call it
+                    // brings the invocation like a macro expanded fn does
+                    StructType::Macro(_) => {
+                        calls.insert(resolved_path.clone());
+                    }
+                    _ => {}
+                }
+            }
+            // enum variants and assoc fns are written after their type
+            if let Some(index) = resolved_path.rfind("::") {
+                let type_name = resolved_path[..index].to_string();
+                if structs.contains_key(&type_name) {
+                    assoc_paths.push((type_name.clone(), resolved_path[index + 2..].to_string()));
+                    types.insert(type_name);
+                }
+            }
+        }
+    }
+    for (complete_fn_name, fn_data) in fns.iter() {
+        let (self_type, trait_name) = match get_impl_names(fn_data) {
+            Some(impl_names) => impl_names,
+            None => continue,
+        };
+        let is_assoc_call = assoc_paths
+            .iter()
+            .any(|(type_name, fn_name)| type_name.eq(&self_type) && fn_name.eq(&fn_data.fn_name));
+        let is_method_call =
+            visitor.methods.contains(&fn_data.fn_name) && types.contains(&self_type);
+        if is_assoc_call || is_method_call {
+            calls.insert(complete_fn_name.clone());
+            if let Some(trait_name) = &trait_name {
+                impl_calls.insert(get_impl_call(&self_type, trait_name, &fn_data.fn_name));
+            }
+        }
+    }
+    // default fns of the traits the named types implement, and of the trait the fn is in
+    for (complete_fn_name, fn_data) in fns.iter() {
+        if let FnType::TraitFn(_, trait_item) = &fn_data.fn_type {
+            if !visitor.methods.contains(&fn_data.fn_name) {
+                continue;
+            }
+            let trait_name = trait_item.get_trait_name().get_import_name().to_string();
+            if scope.trait_name.as_ref() == Some(&trait_name) {
+                calls.insert(complete_fn_name.clone());
+            }
+            for other_fn_data in fns.values() {
+                if let Some((self_type, Some(impl_trait_name))) = get_impl_names(other_fn_data) {
+                    if impl_trait_name.eq(&trait_name) && types.contains(&self_type) {
+                        calls.insert(complete_fn_name.clone());
+                        impl_calls.insert(get_impl_call(&self_type, &trait_name, &fn_data.fn_name));
+                    }
+                }
+            }
+        }
+    }
+    // a method of an unknown receiver is a method of that name on a type the fn names, or
+    // the only method of that name, a trait fn alone does not tell the receiver
+    for method in visitor.methods.iter() {
+        let has_call = calls.iter().any(|call| {
+            fns.get(call).is_some_and(|fn_data| {
+                fn_data.fn_name.eq(method) && matches!(fn_data.fn_type, FnType::ImplFn(_, _))
+            })
+        });
+        if has_call {
+            continue;
+        }
+        let candidates: Vec<(&String, &FnData)> = fns
+            .iter()
+            .filter(|(_, fn_data)| {
+                fn_data.fn_name.eq(method) && !matches!(fn_data.fn_type, FnType::Fn(_))
+            })
+            .collect();
+        let known_candidates: Vec<(&String, &FnData)> = candidates
+            .iter()
+            .filter(|(_, fn_data)| match &fn_data.fn_type {
+                FnType::TraitFn(_, trait_item) => {
+                    types.contains(&trait_item.get_trait_name().get_import_name().to_string())
+                }
+                _ => {
+                    get_impl_names(fn_data).is_some_and(|(self_type, _)| types.contains(&self_type))
+                }
+            })
+            .copied()
+            .collect();
+        let resolved_candidates = if !known_candidates.is_empty() {
+            known_candidates
+        } else if candidates.len() == 1 {
+            candidates
+        } else {
+            continue;
+        };
+        for (complete_fn_name, fn_data) in resolved_candidates {
+            calls.insert(complete_fn_name.clone());
+            if let Some((self_type, Some(trait_name))) = get_impl_names(fn_data) {
+                impl_calls.insert(get_impl_call(&self_type, &trait_name, method));
+            }
+        }
+    }
+
+    data.calls = calls.into_iter().collect();
+    data.types = types.into_iter().collect();
+    data.impl_calls = impl_calls.into_iter().collect();
+    data.statics = statics.into_iter().collect();
+    data.consts = consts.into_iter().collect();
+    data.unsafe_info.is_unsafe_fn = sig.unsafety.is_some();
+    visitor.unsafe_blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collect_context::crate_context::CrateContext;
+    use call_chain::analysis::sourceinfo::SourceInfo;
+    use std::{env::temp_dir, fs};
+    use syn::{parse_str, ItemFn};
+
+    const LIB_CODE: &str = "pub static LIMIT: u32 = 3;
+pub const SCALE: u32 = 2;
+pub struct Circle { pub r: f64 }
+impl Circle {
+    pub fn area(&self) -> f64 { self.r * self.r }
+}
+pub struct Square(pub f64);
+impl Square {
+    pub fn area(&self) -> f64 { self.0 * self.0 }
+}
+pub struct Counter(pub u32);
+impl Counter {
+    pub fn tick(&mut self) { self.0 += 1; }
+}
+pub fn make() -> Counter { Counter(0) }
+";
+
+    fn get_data(dir_name: &str, fn_code: &str) -> CallsAndTypes {
+        let crate_path = temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&crate_path);
+        fs::create_dir_all(crate_path.join("src")).unwrap();
+        fs::write(
+            crate_path.join("Cargo.toml"),
+            "[package]\nname = \"sample\"\n",
+        )
+        .unwrap();
+        fs::write(crate_path.join("src/lib.rs"), LIB_CODE).unwrap();
+        let mut crate_context = CrateContext::new(&crate_path);
+        crate_context.parse_crate();
+        crate_context.change_all_names();
+        let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+        let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
+        crate_context.get_result(&mut fns, &mut structs);
+
+        let item_fn = parse_str::<ItemFn>(fn_code).unwrap();
+        let scope = FnScope {
+            crate_name: "sample",
+            mod_tree: "sample",
+            use_trees: &[],
+            self_type: None,
+            self_type_names: Vec::new(),
+            trait_name: None,
+        };
+        let mut data = CallsAndTypes::new(
+            "sample",
+            true,
+            &SourceInfo::new("src/lib.rs", 1, 0, 1, 0),
+            &BTreeSet::new(),
+            &BTreeSet::new(),
+        );
+        get_calls_and_types(
+            &scope,
+            &item_fn.sig,
+            &item_fn.block,
+            &fns,
+            &structs,
+            &mut data,
+        );
+        data
+    }
+
+    #[test]
+    fn receivers_are_resolved_from_named_types_or_unique_methods() {
+        let data = get_data(
+            "rfocxt_syntax_calls",
+            "fn run(circle: &Circle) -> f64 {
+                make().tick();
+                circle.area() * (LIMIT * SCALE) as f64
+            }",
+        );
+        // `Square::area` is left out as the fn names `Circle`, `tick` is the only method of its name
+        assert_eq!(
+            data.calls,
+            vec![
+                "sample::Circle::area",
+                "sample::Counter::tick",
+                "sample::make"
+            ]
+        );
+        assert_eq!(data.types, vec!["sample::Circle"]);
+        assert_eq!(data.statics, vec!["sample::LIMIT"]);
+        assert_eq!(data.consts, vec!["sample::SCALE"]);
+    }
+
+    #[test]
+    fn ambiguous_receivers_are_not_resolved() {
+        let data = get_data(
+            "rfocxt_syntax_calls_ambiguous",
+            "fn pick(shape: Box<dyn Fn() -> f64>) -> f64 { shape().area() }",
+        );
+        assert!(data.calls.is_empty());
+    }
+}
@@ -1,34 +1,47 @@
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
-    fs::{create_dir_all, File},
+    collections::{BTreeMap, BTreeSet},
+    fs::{create_dir_all, read_dir, read_to_string, File, OpenOptions},
     io::{Read, Write},
     path::PathBuf,
     process::exit,
     rc::Rc,
 };
 
-use call_chain::analysis::exporter::CallsAndTypes;
+use call_chain::{
+    analysis::exporter::{CallsAndTypes, ImplCall, PanicSite, UnsafeInfo},
+    utils::get_output_file_name,
+};
 use prettyplease::unparse;
 use quote::quote;
 use regex::Regex;
 use syn::{
+    parse::Parser,
     parse2,
+    punctuated::Punctuated,
     visit::{self, Visit},
-    Attribute, Expr, Fields, FieldsNamed, GenericParam, Generics, Item, Lit, Meta, Path, Stmt,
-    Type, TypeParamBound, UseTree as SynUseTree, Visibility,
+    Attribute, Expr, ExprMethodCall, ExprPath, Field, Fields, FieldsNamed, FnArg, ForeignItem,
+    GenericArgument, GenericParam, Generics, Item, ItemFn, ItemMacro, Lit, Macro, Meta, Path,
+    PathArguments, QSelf, ReturnType, Signature, Stmt, Token, Type, TypeParamBound, TypePath,
+    UseTree as SynUseTree, Visibility,
 };
 
 use super::{
+    config::{KeepDocs, Render},
     crate_context::CrateContext,
+    doctest::{get_doc_string, parse_doctests, Doctest},
     items_context::{
-        ConstItem, EnumItem, FnItem, FunctionItem, ImplConstItem, ImplFnItem, ImplItem,
-        ImplTypeItem, ModItem, MyPath, MyVisibility, Name, StaticItem, StructItem, TraitAliasItem,
+        ConstItem, EnumItem, FnItem, ForeignFnItem, ForeignModItem, ForeignStaticItem,
+        FunctionItem, ImplConstItem, ImplFnItem, ImplItem, ImplTypeItem, MacroItem, ModItem,
+        MyPath, MyVisibility, Name, SelfType, StaticItem, StructItem, TraitAliasItem,
         TraitConstItem, TraitFnItem, TraitItem, TraitTypeItem, TypeItem, UnionItem, UseItem,
         UseTree,
     },
     mod_context::ModContext,
-    result::{FnData, FnType, StructData, StructType},
+    result::{FnData, FnType, IndexRecord, ManifestEntry, StructData, StructType},
+    syntax_calls::{get_calls_and_types, resolve_path, FnScope},
+    test_scaffold::{get_integration_uses, get_test_scaffold},
+    verbatim::{get_verbatim_item, indent},
 };
 
 use syn::ImplItem as SynImplItem;
@@ -85,6 +98,130 @@ fn delete_doc_attributes(attrs: &Vec<Attribute>) -> Vec<Attribute> {
     no_doc_attrs
 }
 
+fn keep_fn_docs(keep_docs: &KeepDocs, complete_name: &String, focal_name: &String) -> bool {
+    match keep_docs {
+        KeepDocs::None => false,
+        KeepDocs::Focal => complete_name.eq(focal_name),
+        KeepDocs::Fns | KeepDocs::All => true,
+    }
+}
+
+fn delete_item_doc_attributes(item: &mut Item) {
+    match item {
+        Item::Const(item_const) => item_const.attrs = delete_doc_attributes(&item_const.attrs),
+        Item::TraitAlias(item_trait_alias) => {
+            item_trait_alias.attrs = delete_doc_attributes(&item_trait_alias.attrs)
+        }
+        Item::Use(item_use) => item_use.attrs = delete_doc_attributes(&item_use.attrs),
+        Item::Mod(item_mod) => item_mod.attrs = delete_doc_attributes(&item_mod.attrs),
+        Item::Static(item_static) => item_static.attrs = delete_doc_attributes(&item_static.attrs),
+        Item::Type(item_type) => item_type.attrs = delete_doc_attributes(&item_type.attrs),
+        Item::Struct(item_struct) => item_struct.attrs = delete_doc_attributes(&item_struct.attrs),
+        Item::Enum(item_enum) => item_enum.attrs = delete_doc_attributes(&item_enum.attrs),
+        Item::Union(item_union) => item_union.attrs = delete_doc_attributes(&item_union.attrs),
+        Item::Impl(item_impl) => item_impl.attrs = delete_doc_attributes(&item_impl.attrs),
+        Item::Fn(item_fn) => item_fn.attrs = delete_doc_attributes(&item_fn.attrs),
+        Item::Trait(item_trait) => item_trait.attrs = delete_doc_attributes(&item_trait.attrs),
+        Item::Macro(item_macro) => item_macro.attrs = delete_doc_attributes(&item_macro.attrs),
+        Item::ForeignMod(item_foreign_mod) => {
+            item_foreign_mod.attrs = delete_doc_attributes(&item_foreign_mod.attrs);
+            for foreign_item in item_foreign_mod.items.iter_mut() {
+                match foreign_item {
+                    ForeignItem::Fn(item_fn) => {
+                        item_fn.attrs = delete_doc_attributes(&item_fn.attrs)
+                    }
+                    ForeignItem::Static(item_static) => {
+                        item_static.attrs = delete_doc_attributes(&item_static.attrs)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn get_item_attrs(item: &Item) -> Vec<Attribute> {
+    match item {
+        Item::Type(item_type) => item_type.attrs.clone(),
+        Item::Struct(item_struct) => item_struct.attrs.clone(),
+        Item::Enum(item_enum) => item_enum.attrs.clone(),
+        Item::Union(item_union) => item_union.attrs.clone(),
+        Item::Impl(item_impl) => item_impl.attrs.clone(),
+        Item::Fn(item_fn) => item_fn.attrs.clone(),
+        Item::Trait(item_trait) => item_trait.attrs.clone(),
+        _ => Vec::new(),
+    }
+}
+
+fn push_doctests(name: &String, attrs: &Vec<Attribute>, doctests: &mut Vec<(String, Doctest)>) {
+    for doctest in parse_doctests(&get_doc_string(attrs)) {
+        doctests.push((name.clone(), doctest));
+    }
+}
+
+fn impl_to_item_with_docs(impl_item: &ImplItem, keep_docs: &KeepDocs, focal_name: &String) -> Item {
+    let keep_all = matches!(keep_docs, KeepDocs::All);
+    let mut item = impl_item.to_item();
+    if !keep_all {
+        delete_item_doc_attributes(&mut item);
+    }
+    if let Item::Impl(item_impl) = &mut item {
+        let mut impl_fn_items = impl_item.get_fns().iter();
+        for member in item_impl.items.iter_mut() {
+            match member {
+                SynImplItem::Const(item_const) if !keep_all => {
+                    item_const.attrs = delete_doc_attributes(&item_const.attrs);
+                }
+                SynImplItem::Type(item_type) if !keep_all => {
+                    item_type.attrs = delete_doc_attributes(&item_type.attrs);
+                }
+                SynImplItem::Fn(item_fn) => {
+                    let impl_fn_item = impl_fn_items.next().unwrap();
+                    if !keep_fn_docs(keep_docs, &impl_fn_item.get_complete_name(), focal_name) {
+                        item_fn.attrs = delete_doc_attributes(&item_fn.attrs);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    item
+}
+
+fn trait_to_item_with_docs(
+    trait_item: &TraitItem,
+    keep_docs: &KeepDocs,
+    focal_name: &String,
+) -> Item {
+    let keep_all = matches!(keep_docs, KeepDocs::All);
+    let mut item = trait_item.to_item();
+    if !keep_all {
+        delete_item_doc_attributes(&mut item);
+    }
+    if let Item::Trait(item_trait) = &mut item {
+        let mut trait_fn_items = trait_item.get_fns().iter();
+        for member in item_trait.items.iter_mut() {
+            match member {
+                SynTraitItem::Const(item_const) if !keep_all => {
+                    item_const.attrs = delete_doc_attributes(&item_const.attrs);
+                }
+                SynTraitItem::Type(item_type) if !keep_all => {
+                    item_type.attrs = delete_doc_attributes(&item_type.attrs);
+                }
+                SynTraitItem::Fn(item_fn) => {
+                    let trait_fn_item = trait_fn_items.next().unwrap();
+                    if !keep_fn_docs(keep_docs, &trait_fn_item.get_complete_name(), focal_name) {
+                        item_fn.attrs = delete_doc_attributes(&item_fn.attrs);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    item
+}
+
 fn parse_visibility(visibility: &Visibility) -> MyVisibility {
     match visibility {
         Visibility::Public(_) => MyVisibility::PubT,
@@ -96,8 +233,13 @@ fn parse_visibility(visibility: &Visibility) -> MyVisibility {
                     paths.push(p.ident.to_string());
                 }
                 MyVisibility::PubI(MyPath::new(&paths.join("::")))
-            } else {
+            } else if restricted.path.is_ident("crate") {
                 MyVisibility::PubS
+            } else {
+                // `pub(super)` and `pub(self)`
+                MyVisibility::PubI(MyPath::new(
+                    &restricted.path.get_ident().unwrap().to_string(),
+                ))
             }
         }
         Visibility::Inherited => MyVisibility::Pri,
@@ -125,6 +267,72 @@ impl<'ast> Visit<'ast> for PathVisitor {
     }
 }
 
+// the names used as `Self::name`, `<Self as Trait>::name` or `self.name(...)`
+struct SelfReferenceVisitor {
+    names: Vec<String>,
+}
+
+impl SelfReferenceVisitor {
+    fn new() -> Self {
+        SelfReferenceVisitor { names: Vec::new() }
+    }
+
+    fn insert_name(&mut self, name: String) {
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+    }
+
+    fn visit_qself_path(&mut self, qself: &Option<QSelf>, path: &Path) {
+        if let Some(qself) = qself {
+            if let Type::Path(type_path) = &*qself.ty {
+                if type_path.qself.is_none() && type_path.path.is_ident("Self") {
+                    self.insert_name(path.segments.last().unwrap().ident.to_string());
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for SelfReferenceVisitor {
+    fn visit_path(&mut self, node: &'ast Path) {
+        if node.segments.len() > 1 && node.segments[0].ident == "Self" {
+            self.insert_name(node.segments[1].ident.to_string());
+        }
+        visit::visit_path(self, node);
+    }
+
+    fn visit_type_path(&mut self, node: &'ast TypePath) {
+        self.visit_qself_path(&node.qself, &node.path);
+        visit::visit_type_path(self, node);
+    }
+
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        self.visit_qself_path(&node.qself, &node.path);
+        visit::visit_expr_path(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        if let Expr::Path(expr_path) = &*node.receiver {
+            if expr_path.path.is_ident("self") {
+                self.insert_name(node.method.to_string());
+            }
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    // default bodies often use `self` in the args of `format!`, `assert!` and the like
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        let parser = Punctuated::<Expr, Token![,]>::parse_terminated;
+        if let Ok(exprs) = parser.parse2(node.tokens.clone()) {
+            for expr in exprs.iter() {
+                self.visit_expr(expr);
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+}
+
 fn visit_generics(generics: &Generics, applications: &mut Vec<String>) {
     let mut visitor = PathVisitor::new();
     for genericparam in generics.params.iter() {
@@ -177,13 +385,116 @@ fn visit_fields_named(fields_named: &FieldsNamed, applications: &mut Vec<String>
     applications.dedup();
 }
 
+fn visit_signature(sig: &Signature, applications: &mut Vec<String>) {
+    let mut visitor = PathVisitor::new();
+    for input in sig.inputs.iter() {
+        if let FnArg::Typed(pat_type) = input {
+            visitor.visit_type(&pat_type.ty);
+        }
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        visitor.visit_type(ty);
+    }
+    applications.extend(visitor.paths);
+    applications.sort();
+    applications.dedup();
+}
+
+// a relative type is a bare name, the type of that name in the mod it is used in comes first,
+// otherwise it can be any type of that name in the mod trees
+fn get_complete_type_names(
+    type_name: &String,
+    parent_mod: &String,
+    mod_trees: &[String],
+    structs: &BTreeMap<String, StructData>,
+) -> Vec<String> {
+    let type_path = MyPath::new(type_name);
+    let complete_type_name = MyPath::new(parent_mod).connect(&type_path).to_string();
+    if structs.contains_key(&complete_type_name) {
+        return vec![complete_type_name];
+    }
+    let mut complete_type_names: Vec<String> = Vec::new();
+    if structs.contains_key(type_name) {
+        complete_type_names.push(type_name.clone());
+    }
+    for mod_tree in mod_trees.iter() {
+        let complete_type_name = MyPath::new(mod_tree).connect(&type_path).to_string();
+        if structs.contains_key(&complete_type_name)
+            && !complete_type_names.contains(&complete_type_name)
+        {
+            complete_type_names.push(complete_type_name);
+        }
+    }
+    complete_type_names
+}
+
+// follows the field, variant payload and generic arg types of the self and signature types
+// `type_depth` levels deep, every type is followed once so recursive types stop
+fn add_relative_types(
+    data: &mut CallsAndTypes,
+    self_type_names: Vec<String>,
+    sig: &Signature,
+    mod_tree: &String,
+    type_depth: usize,
+    mod_trees: &[String],
+    structs: &BTreeMap<String, StructData>,
+) {
+    let mut current_types: Vec<String> = self_type_names;
+    let mut signature_types: Vec<String> = Vec::new();
+    visit_signature(sig, &mut signature_types);
+    for signature_type in signature_types.iter() {
+        current_types.extend(get_complete_type_names(
+            signature_type,
+            mod_tree,
+            mod_trees,
+            structs,
+        ));
+    }
+    let mut followed_types: BTreeSet<String> = BTreeSet::new();
+    for _ in 0..type_depth {
+        let mut next_types: Vec<String> = Vec::new();
+        for current_type in current_types.iter() {
+            if !followed_types.insert(current_type.clone()) {
+                continue;
+            }
+            let relative_types = match structs.get(current_type) {
+                Some(StructData {
+                    struct_type: StructType::Struct(struct_item),
+                    ..
+                }) => struct_item.get_relative_types(),
+                Some(StructData {
+                    struct_type: StructType::Enum(enum_item),
+                    ..
+                }) => enum_item.get_relative_types(),
+                Some(StructData {
+                    struct_type: StructType::Union(union_item),
+                    ..
+                }) => union_item.get_relative_types(),
+                _ => continue,
+            };
+            let parent_mod = get_parent_mod(current_type);
+            for relative_type in relative_types.iter() {
+                for complete_type_name in
+                    get_complete_type_names(relative_type, &parent_mod, mod_trees, structs)
+                {
+                    if !data.types.contains(&complete_type_name) {
+                        data.types.push(complete_type_name.clone());
+                    }
+                    next_types.push(complete_type_name);
+                }
+            }
+        }
+        current_types = next_types;
+    }
+}
+
 fn add_new_calls_and_types(data: &mut CallsAndTypes, mod_trees: &Vec<String>) {
     let re_impl = Regex::new(r"<impl\s([^>]+)>").unwrap();
     let re_as = Regex::new(r"<([^>\s]+)\sas\s([^>\s]+)>").unwrap();
     let re_trait_bound = Regex::new(r"(::<[^>\s]+[,\s[^>\s]+]*>)").unwrap();
     let re_struct = Regex::new(r"(<[^>\s]+[,\s[^>\s]+]*>)").unwrap();
-    let mut new_calls: HashSet<String> = HashSet::new();
-    let mut new_types: HashSet<String> = HashSet::new();
+    let mut new_calls: BTreeSet<String> = BTreeSet::new();
+    let mut new_types: BTreeSet<String> = BTreeSet::new();
     for call in data.calls.iter() {
         for caps in re_impl.captures_iter(&call) {
             let content = caps[1].to_string();
@@ -238,7 +549,7 @@ fn add_new_calls_and_types(data: &mut CallsAndTypes, mod_trees: &Vec<String>) {
             data.calls.push(new_call);
         }
     }
-    new_calls = HashSet::new();
+    new_calls = BTreeSet::new();
     for call in data.calls.iter() {
         for mod_tree in mod_trees.iter() {
             let mod_tree_path = MyPath::new(mod_tree);
@@ -306,7 +617,7 @@ fn add_new_calls_and_types(data: &mut CallsAndTypes, mod_trees: &Vec<String>) {
             data.types.push(new_type);
         }
     }
-    new_types = HashSet::new();
+    new_types = BTreeSet::new();
     for a_type in data.types.iter() {
         for mod_tree in mod_trees.iter() {
             let mod_tree_path = MyPath::new(mod_tree);
@@ -325,17 +636,312 @@ fn add_new_calls_and_types(data: &mut CallsAndTypes, mod_trees: &Vec<String>) {
             data.types.push(new_type);
         }
     }
+    add_mod_tree_prefixes(&mut data.statics, mod_trees);
+    add_mod_tree_prefixes(&mut data.consts, mod_trees);
+}
+
+// statics and consts are plain paths, they only need the mod trees in front
+fn add_mod_tree_prefixes(names: &mut Vec<String>, mod_trees: &Vec<String>) {
+    let mut new_names: BTreeSet<String> = BTreeSet::new();
+    for name in names.iter() {
+        for mod_tree in mod_trees.iter() {
+            let mod_tree_path = MyPath::new(mod_tree);
+            let name_path = MyPath::new(name);
+            let new_name = mod_tree_path.connect(&name_path);
+            new_names.insert(new_name.to_string());
+        }
+    }
+    for new_name in new_names {
+        if !names.contains(&new_name) {
+            names.push(new_name);
+        }
+    }
+}
+
+// the text between the `<` at `start` and the `>` that closes it
+fn get_angle_bracketed(text: &str, start: usize) -> Option<&str> {
+    let mut depth = 0;
+    let mut last_char = ' ';
+    for (index, c) in text[start..].char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if last_char != '-' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start + 1..start + index]);
+                }
+            }
+            _ => {}
+        }
+        last_char = c;
+    }
+    None
+}
+
+// `<X as T>::f`, `<X>::f` and `m::<impl X>::f` are split into the self type, the trait and the fn name
+fn split_impl_call(call: &str) -> Option<(String, Option<String>, String)> {
+    let start = match call.starts_with('<') {
+        true => 0,
+        false => call.find("<impl ")?,
+    };
+    let content = get_angle_bracketed(call, start)?;
+    let rest = call[start + content.len() + 2..].strip_prefix("::")?;
+    let fn_name = rest.split("::").next()?.to_string();
+    if let Some(self_type) = content.strip_prefix("impl ") {
+        return Some((self_type.to_string(), None, fn_name));
+    }
+    let mut depth = 0;
+    for (index, c) in content.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ' ' if depth == 0 && content[index..].starts_with(" as ") => {
+                return Some((
+                    content[..index].to_string(),
+                    Some(content[index + 4..].to_string()),
+                    fn_name,
+                ));
+            }
+            _ => {}
+        }
+    }
+    Some((content.to_string(), None, fn_name))
+}
+
+// paths are cut to their last segment and lifetimes are removed,
+// so that a MIR type can be compared with `SelfType::get_short_type_string`
+fn get_short_type_string(mir_type: &str) -> String {
+    let chars: Vec<char> = mir_type.chars().collect();
+    let mut short_type = String::new();
+    let mut ident = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else if c == ':' && index + 1 < chars.len() && chars[index + 1] == ':' {
+            ident.clear();
+            index += 1;
+        } else if c == '\'' {
+            index += 1;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            if index < chars.len() && chars[index] == ',' {
+                index += 1;
+            }
+            while index < chars.len() && chars[index] == ' ' {
+                index += 1;
+            }
+            continue;
+        } else {
+            short_type += &ident;
+            ident.clear();
+            short_type.push(c);
+        }
+        index += 1;
+    }
+    short_type += &ident;
+    // `(dyn Shape + 'static)` is named `dyn Shape` in the impl
+    let short_type = short_type
+        .replace(" + )", ")")
+        .replace(" + >", ">")
+        .replace("<>", "");
+    let short_type = short_type.trim().trim_end_matches(" +");
+    match short_type.strip_prefix("(dyn ") {
+        Some(trait_object) if short_type.ends_with(')') => {
+            format!("dyn {}", &trait_object[..trait_object.len() - 1])
+        }
+        _ => short_type.to_string(),
+    }
+}
+
+fn get_short_trait_name(mir_trait: &str) -> String {
+    let mir_trait = match mir_trait.find('<') {
+        Some(index) => &mir_trait[..index],
+        None => mir_trait,
+    };
+    mir_trait.rsplit("::").next().unwrap().trim().to_string()
+}
+
+// impls on references, slices, arrays, tuples, trait objects, foreign types and generic params
+// have no name that MIR uses, they are matched by the shape of their self type,
+// a blanket impl is only taken if no other impl matches
+fn get_impl_fn_for_call<'a>(call: &str, fns: &'a BTreeMap<String, FnData>) -> Option<&'a FnData> {
+    let (self_type, trait_name, fn_name) = split_impl_call(call)?;
+    let self_type = get_short_type_string(&self_type);
+    let trait_name = trait_name.map(|trait_name| get_short_trait_name(&trait_name));
+    if self_type.is_empty() || trait_name == Some(String::new()) {
+        return None;
+    }
+    let mut blanket_fn_data: Option<&FnData> = None;
+    for fn_data in fns.values() {
+        if let FnType::ImplFn(impl_fn_item, impl_item) = &fn_data.fn_type {
+            if !impl_fn_item.get_name().eq(&fn_name) {
+                continue;
+            }
+            let impl_self_type = impl_item.get_self_type();
+            let struct_import_name = impl_item.get_struct_name().get_import_name().to_string();
+            if impl_self_type.is_path() && !struct_import_name.eq("") {
+                continue;
+            }
+            if get_impl_trait_name(impl_item) != trait_name {
+                continue;
+            }
+            if impl_self_type.get_short_type_string().eq(&self_type) {
+                return Some(fn_data);
+            }
+            if let (SelfType::Param(_), None) = (impl_self_type, blanket_fn_data) {
+                blanket_fn_data = Some(fn_data);
+            }
+        }
+    }
+    blanket_fn_data
+}
+
+fn get_impl_trait_name(impl_item: &ImplItem) -> Option<String> {
+    impl_item
+        .get_item()
+        .trait_
+        .as_ref()
+        .map(|(_, path, _)| path.segments.last().unwrap().ident.to_string())
+}
+
+fn get_impl_trait_import_name(impl_item: &ImplItem) -> Option<String> {
+    impl_item
+        .get_trait_name()
+        .as_ref()
+        .map(|trait_name| trait_name.get_import_name().to_string())
+}
+
+fn get_impl_call_trait_name(impl_call: &ImplCall) -> Option<String> {
+    let (_, trait_name, _) = split_impl_call(&impl_call.call)?;
+    trait_name.map(|trait_name| get_short_trait_name(&trait_name))
+}
+
+fn get_impl_call_fn_name(impl_call: &ImplCall) -> String {
+    impl_call.callee.rsplit("::").next().unwrap().to_string()
+}
+
+// the impl fn with the self type that binds the fewest generic params to the self type of the call
+fn get_impl_fn_for_impl_call<'a>(
+    impl_call: &ImplCall,
+    fns: &'a BTreeMap<String, FnData>,
+) -> Option<&'a String> {
+    let self_type = SelfType::from_mir_type(&impl_call.self_type)?;
+    let fn_name = get_impl_call_fn_name(impl_call);
+    let trait_name = get_impl_call_trait_name(impl_call);
+    let mut impl_fn: Option<(&String, usize)> = None;
+    for (complete_name, fn_data) in fns.iter() {
+        if let FnType::ImplFn(impl_fn_item, impl_item) = &fn_data.fn_type {
+            if !impl_fn_item.get_name().eq(&fn_name) || get_impl_trait_name(impl_item) != trait_name
+            {
+                continue;
+            }
+            let mut bindings: BTreeMap<String, String> = BTreeMap::new();
+            if !impl_item.get_self_type().bind(&self_type, &mut bindings) {
+                continue;
+            }
+            match impl_fn {
+                Some((_, binding_num)) if binding_num <= bindings.len() => {}
+                _ => impl_fn = Some((complete_name, bindings.len())),
+            }
+        }
+    }
+    impl_fn.map(|(complete_name, _)| complete_name)
+}
+
+// the trait impl a call of a default trait fn goes through, it has no fn of that name
+fn get_impl_for_impl_call<'a>(
+    impl_call: &ImplCall,
+    fns: &'a BTreeMap<String, FnData>,
+) -> Option<&'a ImplItem> {
+    let self_type = SelfType::from_mir_type(&impl_call.self_type)?;
+    let trait_name = get_impl_call_trait_name(impl_call);
+    trait_name.as_ref()?;
+    let mut impl_item_found: Option<(&ImplItem, usize)> = None;
+    for fn_data in fns.values() {
+        if let FnType::ImplFn(_, impl_item) = &fn_data.fn_type {
+            if get_impl_trait_name(impl_item) != trait_name {
+                continue;
+            }
+            let mut bindings: BTreeMap<String, String> = BTreeMap::new();
+            if !impl_item.get_self_type().bind(&self_type, &mut bindings) {
+                continue;
+            }
+            match impl_item_found {
+                Some((_, binding_num)) if binding_num <= bindings.len() => {}
+                _ => impl_item_found = Some((impl_item, bindings.len())),
+            }
+        }
+    }
+    impl_item_found.map(|(impl_item, _)| impl_item)
+}
+
+// an impl fn found by name is only kept if it applies to one of the calls of that fn,
+// e.g. `impl Foo<u16>` is dropped for `Foo::<u8>::get`
+fn is_impl_applied(
+    impl_fn_item: &ImplFnItem,
+    impl_item: &ImplItem,
+    impl_calls: &[ImplCall],
+) -> bool {
+    let trait_name = get_impl_trait_name(impl_item);
+    let mut has_impl_call = false;
+    for impl_call in impl_calls.iter() {
+        if !get_impl_call_fn_name(impl_call).eq(&impl_fn_item.get_name())
+            || get_impl_call_trait_name(impl_call) != trait_name
+        {
+            continue;
+        }
+        let self_type = match SelfType::from_mir_type(&impl_call.self_type) {
+            Some(self_type) => self_type,
+            None => return true,
+        };
+        has_impl_call = true;
+        let mut bindings: BTreeMap<String, String> = BTreeMap::new();
+        if impl_item.get_self_type().bind(&self_type, &mut bindings) {
+            return true;
+        }
+    }
+    !has_impl_call
 }
 
 fn get_syntax(
     data: &CallsAndTypes,
     syntax_context: &mut SyntaxContext,
-    fns: &HashMap<String, FnData>,
-    structs: &HashMap<String, StructData>,
+    fns: &BTreeMap<String, FnData>,
+    structs: &BTreeMap<String, StructData>,
+    trait_default_methods: bool,
 ) {
-    for call in data.calls.iter() {
-        let fn_data = fns.get(call);
+    // the impls resolved from the generic args of the calls come first
+    let mut calls: Vec<String> = Vec::new();
+    for impl_call in data.impl_calls.iter() {
+        if let Some(fn_name) = get_impl_fn_for_impl_call(impl_call, fns) {
+            if !calls.contains(fn_name) {
+                calls.push(fn_name.clone());
+            }
+        } else if let Some(impl_item) = get_impl_for_impl_call(impl_call, fns) {
+            let has_impl = syntax_context
+                .impls
+                .iter()
+                .any(|has_impl_item| has_impl_item.get_item().eq(impl_item.get_item()));
+            if !has_impl {
+                syntax_context.impls.push(impl_item.clone());
+            }
+        }
+    }
+    calls.extend(data.calls.iter().cloned());
+    for call in calls.iter() {
+        let fn_data = match fns.get(call) {
+            Some(fn_data) => Some(fn_data),
+            None => get_impl_fn_for_call(call, fns),
+        };
         if let Some(fn_data) = fn_data {
+            if let FnType::ImplFn(impl_fn_item, impl_item) = &fn_data.fn_type {
+                if !is_impl_applied(impl_fn_item, impl_item, &data.impl_calls) {
+                    continue;
+                }
+            }
             match &fn_data.fn_type {
                 FnType::Fn(fn_item) => {
                     if !syntax_context.functions.contains(&fn_item) {
@@ -346,7 +952,9 @@ fn get_syntax(
                     let mut has_impl = false;
                     for has_impl_item in syntax_context.impls.iter_mut() {
                         if has_impl_item.get_item().eq(&impl_item.get_item()) {
-                            has_impl_item.insert_function(&impl_fn_item);
+                            if !has_impl_item.get_fns().contains(impl_fn_item) {
+                                has_impl_item.insert_function(&impl_fn_item);
+                            }
                             has_impl = true;
                         }
                     }
@@ -355,27 +963,32 @@ fn get_syntax(
                         impl_item.insert_function(&impl_fn_item);
                         syntax_context.impls.push(impl_item);
                     }
-                    let struct_item_string =
-                        impl_item.get_struct_name().get_import_name().to_string();
-                    let struct_item = structs.get(&struct_item_string);
-                    if let Some(struct_item) = struct_item {
-                        match &struct_item.struct_type {
-                            StructType::Struct(struct_item) => {
-                                if !syntax_context.structs.contains(&struct_item) {
-                                    syntax_context.structs.push(struct_item.clone());
+                    let mut struct_item_strings: Vec<String> =
+                        vec![impl_item.get_struct_name().get_import_name().to_string()];
+                    impl_item
+                        .get_self_type()
+                        .get_path_names(&mut struct_item_strings);
+                    for struct_item_string in struct_item_strings.iter() {
+                        let struct_item = structs.get(struct_item_string);
+                        if let Some(struct_item) = struct_item {
+                            match &struct_item.struct_type {
+                                StructType::Struct(struct_item) => {
+                                    if !syntax_context.structs.contains(&struct_item) {
+                                        syntax_context.structs.push(struct_item.clone());
+                                    }
                                 }
-                            }
-                            StructType::Enum(enum_item) => {
-                                if !syntax_context.enums.contains(&enum_item) {
-                                    syntax_context.enums.push(enum_item.clone());
+                                StructType::Enum(enum_item) => {
+                                    if !syntax_context.enums.contains(&enum_item) {
+                                        syntax_context.enums.push(enum_item.clone());
+                                    }
                                 }
-                            }
-                            StructType::Union(union_item) => {
-                                if !syntax_context.unions.contains(&union_item) {
-                                    syntax_context.unions.push(union_item.clone());
+                                StructType::Union(union_item) => {
+                                    if !syntax_context.unions.contains(&union_item) {
+                                        syntax_context.unions.push(union_item.clone());
+                                    }
                                 }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     }
                     // let trait_item_name = impl_item.get_trait_name();
@@ -424,6 +1037,78 @@ fn get_syntax(
                     //     }
                     // }
                 }
+                FnType::ForeignFn(foreign_fn_item, foreign_mod_item) => {
+                    let mut has_foreign_mod = false;
+                    for has_foreign_mod_item in syntax_context.foreign_mods.iter_mut() {
+                        if has_foreign_mod_item
+                            .get_item()
+                            .eq(foreign_mod_item.get_item())
+                        {
+                            if !has_foreign_mod_item.get_fns().contains(foreign_fn_item) {
+                                has_foreign_mod_item.insert_function(foreign_fn_item);
+                            }
+                            has_foreign_mod = true;
+                        }
+                    }
+                    if !has_foreign_mod {
+                        let mut foreign_mod_item = foreign_mod_item.clone();
+                        foreign_mod_item.insert_function(foreign_fn_item);
+                        syntax_context.foreign_mods.push(foreign_mod_item);
+                    }
+                }
+            }
+        } else if let Some(struct_data) = structs.get(call) {
+            // fns expanded from a macro invocation bring the invocation
+            if let StructType::Macro(macro_item) = &struct_data.struct_type {
+                syntax_context.insert_macro(macro_item);
+            }
+        }
+    }
+    for a_static in data.statics.iter() {
+        if let Some(static_data) = structs.get(a_static) {
+            if let StructType::Macro(macro_item) = &static_data.struct_type {
+                syntax_context.insert_macro(macro_item);
+            }
+            if let StructType::Static(static_item) = &static_data.struct_type {
+                if !syntax_context.statics.contains(static_item) {
+                    syntax_context.statics.push(static_item.clone());
+                }
+            }
+            if let StructType::ForeignStatic(foreign_static_item, foreign_mod_item) =
+                &static_data.struct_type
+            {
+                let mut has_foreign_mod = false;
+                for has_foreign_mod_item in syntax_context.foreign_mods.iter_mut() {
+                    if has_foreign_mod_item
+                        .get_item()
+                        .eq(foreign_mod_item.get_item())
+                    {
+                        if !has_foreign_mod_item
+                            .get_statics()
+                            .contains(foreign_static_item)
+                        {
+                            has_foreign_mod_item.insert_static(foreign_static_item);
+                        }
+                        has_foreign_mod = true;
+                    }
+                }
+                if !has_foreign_mod {
+                    let mut foreign_mod_item = foreign_mod_item.clone();
+                    foreign_mod_item.insert_static(foreign_static_item);
+                    syntax_context.foreign_mods.push(foreign_mod_item);
+                }
+            }
+        }
+    }
+    for a_const in data.consts.iter() {
+        if let Some(const_data) = structs.get(a_const) {
+            if let StructType::Macro(macro_item) = &const_data.struct_type {
+                syntax_context.insert_macro(macro_item);
+            }
+            if let StructType::Const(const_item) = &const_data.struct_type {
+                if !syntax_context.consts.contains(const_item) {
+                    syntax_context.consts.push(const_item.clone());
+                }
             }
         }
     }
@@ -465,23 +1150,30 @@ fn get_syntax(
                     }
                     if !has_trait {
                         let mut trait_item = trait_item.clone();
+                        trait_item.clear();
                         syntax_context.traits.push(trait_item);
                     }
                 }
+                StructType::Static(_) | StructType::Const(_) | StructType::ForeignStatic(_, _) => {}
+                StructType::Macro(macro_item) => {
+                    syntax_context.insert_macro(macro_item);
+                }
             }
         }
     }
+    syntax_context.complete_traits(fns, structs, trait_default_methods);
 }
 
 fn parse_callsandtypes(
     data: &mut CallsAndTypes,
     mod_trees: &Vec<String>,
     syntax_context: &mut SyntaxContext,
-    fns: &HashMap<String, FnData>,
-    structs: &HashMap<String, StructData>,
+    fns: &BTreeMap<String, FnData>,
+    structs: &BTreeMap<String, StructData>,
+    trait_default_methods: bool,
 ) {
     add_new_calls_and_types(data, mod_trees);
-    get_syntax(data, syntax_context, fns, structs);
+    get_syntax(data, syntax_context, fns, structs, trait_default_methods);
 }
 
 // struct PathVisitor {
@@ -631,52 +1323,496 @@ fn parse_callsandtypes(
 //     }
 // }
 
-fn expand_use_tree(
-    tree: &SynUseTree,
-    visibility: &MyVisibility,
-    expand_path: String,
-    expanded_trees: &mut Vec<UseTree>,
-) {
-    match tree {
-        SynUseTree::Path(use_path) => {
-            let mut path_str = String::new();
-            if expand_path != String::new() {
-                path_str = expand_path + "::" + &use_path.ident.to_string();
-            } else {
-                path_str = use_path.ident.to_string();
-            }
-            expand_use_tree(&use_path.tree, visibility, path_str, expanded_trees);
+pub fn get_tests(output_path: &PathBuf, tests: &mut BTreeMap<String, Vec<String>>) {
+    let entries = match read_dir(output_path.join("callsandtypes")) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut test_names: Vec<String> = Vec::new();
+    for entry in entries {
+        let file_name = entry.unwrap().file_name().to_string_lossy().to_string();
+        if let Some(test_name) = file_name.strip_suffix(".json") {
+            test_names.push(test_name.to_string());
         }
-        SynUseTree::Name(use_name) => {
-            let mut path_str = String::new();
-            if expand_path != String::new() {
-                path_str = expand_path + "::" + &use_name.ident.to_string();
-            } else {
-                path_str = use_name.ident.to_string();
-            }
-            let use_tree = UseTree::new(
-                use_name.ident.to_string(),
-                path_str,
-                None,
-                visibility.clone(),
-            );
-            expanded_trees.push(use_tree);
+    }
+    test_names.sort();
+    for test_name in test_names.iter() {
+        let call_file = output_path.join(String::from("callsandtypes/") + test_name + ".json");
+        let contents = read_to_string(call_file).unwrap();
+        let data: CallsAndTypes = serde_json::from_str(&contents).unwrap();
+        if !data.is_test {
+            continue;
         }
-        SynUseTree::Glob(_) => {
-            let mut path_str = String::new();
-            if expand_path != String::new() {
-                path_str = expand_path + "::*";
-            } else {
-                path_str = "*".to_string();
+        for test_call in data.test_calls.iter() {
+            let fn_tests = tests.entry(test_call.clone()).or_insert(Vec::new());
+            if !fn_tests.contains(&data.test_source) {
+                fn_tests.push(data.test_source.clone());
             }
-            let use_tree = UseTree::new("*".to_string(), path_str, None, visibility.clone());
-            expanded_trees.push(use_tree);
         }
-        SynUseTree::Rename(use_rename) => {
-            let mut path_str = String::new();
-            if expand_path != String::new() {
-                path_str = expand_path + "::" + &use_rename.ident.to_string();
-                let use_tree = UseTree::new(
+    }
+}
+
+const MAX_CONSTRUCTOR_HINTS: usize = 3;
+
+// the path of the type a function returns, looking through `Option`, `Result`, `Box`, `Rc`
+// and `Arc`
+fn get_returned_type_path(output: &ReturnType) -> Option<Vec<String>> {
+    let mut ty = match output {
+        ReturnType::Type(_, ty) => &**ty,
+        ReturnType::Default => return None,
+    };
+    loop {
+        let type_path = match ty {
+            Type::Path(type_path) if type_path.qself.is_none() => type_path,
+            _ => return None,
+        };
+        let last = type_path.path.segments.last()?;
+        let name = last.ident.to_string();
+        match name.as_str() {
+            "Option" | "Result" | "Box" | "Rc" | "Arc" => {
+                if let PathArguments::AngleBracketed(arguments) = &last.arguments {
+                    if let Some(GenericArgument::Type(inner_ty)) = arguments.args.first() {
+                        ty = inner_ty;
+                        continue;
+                    }
+                }
+                return None;
+            }
+            _ => {
+                return Some(
+                    type_path
+                        .path
+                        .segments
+                        .iter()
+                        .map(|segment| segment.ident.to_string())
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+// the complete name of the type a fn written in the scope returns, if it is a type of the crate
+fn get_returned_type_name(
+    output: &ReturnType,
+    scope: &FnScope,
+    structs: &BTreeMap<String, StructData>,
+) -> Option<String> {
+    let type_path = get_returned_type_path(output)?;
+    resolve_path(&type_path, scope)
+        .into_iter()
+        .find(|type_name| structs.contains_key(type_name))
+}
+
+fn get_type_string(ty: &Type) -> String {
+    let item: Item = parse2(quote! { type T = #ty; }).unwrap();
+    let code = unparse(&syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![item],
+    });
+    code.trim()
+        .trim_start_matches("type T = ")
+        .trim_end_matches(';')
+        .to_string()
+}
+
+// the signature with `fn name` replaced by the path of the function
+fn get_signature_string(sig: &Signature, fn_path: &String) -> String {
+    let item: Item = parse2(quote! { #sig {} }).unwrap();
+    let code = unparse(&syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![item],
+    });
+    let code = code
+        .trim()
+        .trim_end_matches("{}")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(", )", ")");
+    let fn_name = format!("fn {}", sig.ident);
+    match code.find(&fn_name) {
+        Some(index) => format!(
+            "{}{}{}",
+            &code[..index],
+            fn_path,
+            &code[index + fn_name.len()..]
+        ),
+        None => code,
+    }
+}
+
+fn get_fields_string(fields: &Fields) -> String {
+    match fields {
+        Fields::Named(fields_named) => {
+            let mut field_strings: Vec<String> = Vec::new();
+            for field in fields_named.named.iter() {
+                field_strings.push(format!(
+                    "{}: {}",
+                    field.ident.as_ref().unwrap(),
+                    get_type_string(&field.ty)
+                ));
+            }
+            format!(" {{ {} }}", field_strings.join(", "))
+        }
+        Fields::Unnamed(fields_unnamed) => {
+            let mut field_strings: Vec<String> = Vec::new();
+            for field in fields_unnamed.unnamed.iter() {
+                field_strings.push(get_type_string(&field.ty));
+            }
+            format!("({})", field_strings.join(", "))
+        }
+        Fields::Unit => String::new(),
+    }
+}
+
+fn has_derive(attrs: &Vec<Attribute>, derive_name: &str) -> bool {
+    for attr in attrs.iter() {
+        if !attr.path().is_ident("derive") {
+            continue;
+        }
+        if let Ok(paths) = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated) {
+            if paths
+                .iter()
+                .any(|path| path.segments.last().unwrap().ident.eq(derive_name))
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// the module an item with the visibility is visible in, `None` if it is visible everywhere
+fn get_visibility_scope(
+    visibility: &MyVisibility,
+    def_mod: &str,
+    crate_name: &str,
+) -> Option<String> {
+    match visibility {
+        MyVisibility::PubT => None,
+        MyVisibility::PubS => Some(crate_name.to_string()),
+        MyVisibility::Pri => Some(def_mod.to_string()),
+        MyVisibility::PubI(path) => {
+            let mut scope: Vec<String> = def_mod.split("::").map(|s| s.to_string()).collect();
+            for (i, name) in path.to_string().split("::").enumerate() {
+                match name {
+                    "crate" if i == 0 => scope = vec![crate_name.to_string()],
+                    "super" => {
+                        if scope.len() > 1 {
+                            scope.pop();
+                        }
+                    }
+                    "self" => {}
+                    _ => scope.push(name.to_string()),
+                }
+            }
+            Some(scope.join("::"))
+        }
+    }
+}
+
+// `test_mod` is the module of the unit tests, `None` for integration tests outside of the crate
+fn is_visible_from(
+    visibility: &MyVisibility,
+    def_mod: &str,
+    crate_name: &str,
+    test_mod: &Option<String>,
+) -> bool {
+    match (
+        get_visibility_scope(visibility, def_mod, crate_name),
+        test_mod,
+    ) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(scope), Some(test_mod)) => {
+            test_mod.eq(&scope) || test_mod.starts_with(&(scope + "::"))
+        }
+    }
+}
+
+fn is_non_exhaustive(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("non_exhaustive"))
+}
+
+fn get_parent_mod(import_name: &str) -> String {
+    match import_name.rfind("::") {
+        Some(index) => import_name[..index].to_string(),
+        None => import_name.to_string(),
+    }
+}
+
+// items of `mod_tree` are written first, then every sub mod that has items,
+// wrapped in a `mod` with the visibility it has in the crate
+fn nest_item_texts(
+    mod_tree: &str,
+    item_texts: &[(String, String)],
+    crate_context: &CrateContext,
+) -> String {
+    let mut context = String::new();
+    let mut sub_mods: Vec<String> = Vec::new();
+    let prefix = mod_tree.to_string() + "::";
+    for (item_mod_tree, text) in item_texts.iter() {
+        if item_mod_tree.eq(mod_tree) {
+            context += text;
+        } else if let Some(sub_path) = item_mod_tree.strip_prefix(&prefix) {
+            let sub_mod = sub_path.split("::").next().unwrap().to_string();
+            if !sub_mods.contains(&sub_mod) {
+                sub_mods.push(sub_mod);
+            }
+        }
+    }
+    for sub_mod in sub_mods.iter() {
+        let sub_mod_tree = prefix.clone() + sub_mod;
+        match crate_context.get_mod_visibility(&sub_mod_tree) {
+            MyVisibility::Pri => context += &format!("mod {} {{\n", sub_mod),
+            visibility => context += &format!("{} mod {} {{\n", visibility.get_label(), sub_mod),
+        }
+        context += &indent(&nest_item_texts(&sub_mod_tree, item_texts, crate_context));
+        context += "}\n";
+    }
+    context
+}
+
+fn write_context(
+    output_path: &PathBuf,
+    complete_function_name: &String,
+    mod_tree: &String,
+    fn_type: &FnType,
+    data: &CallsAndTypes,
+    syntax_context: &mut SyntaxContext,
+    crate_context: &CrateContext,
+) {
+    if data.is_test {
+        return;
+    }
+    let fn_coverage = crate_context.get_fn_coverage(complete_function_name, data);
+    if let (Some(threshold), Some(fn_coverage)) = (
+        crate_context.get_config().get_only_uncovered(),
+        &fn_coverage,
+    ) {
+        if fn_coverage.has_data() && fn_coverage.get_percent() >= threshold {
+            return;
+        }
+    }
+    let include_impls = crate_context.get_config().get_include_impls();
+    if include_impls {
+        syntax_context.add_impls_of_types(crate_context);
+    }
+    let fn_name = fn_type.get_complete_name();
+    let mut context = syntax_context.to_string(
+        crate_context.get_config().get_keep_docs(),
+        &fn_name,
+        crate_context,
+    );
+    let tests = crate_context.get_tests_for_fn(complete_function_name);
+    syntax_context.append_tests(&mut context, &fn_name, &tests);
+    if let Some(fn_coverage) = &fn_coverage {
+        if fn_coverage.has_data() {
+            context += &fn_coverage.to_comments(&fn_name);
+        }
+    }
+    let panic_sites = crate_context.get_panic_sites(complete_function_name);
+    syntax_context.append_panic_sites(&mut context, &fn_name, &panic_sites);
+    syntax_context.append_unsafe_info(&mut context, &fn_name, &data.unsafe_info);
+    syntax_context.append_foreign_items(&mut context, &fn_name);
+    if include_impls {
+        syntax_context.append_trait_summary(&mut context, crate_context);
+    }
+    // a fn that is only reachable through a path its tests can not name is tested in its mod
+    let integration_uses = if data.reachable {
+        get_integration_uses(fn_type, &crate_context.get_crate_name(), |def_path| {
+            crate_context.get_public_path(def_path)
+        })
+    } else {
+        None
+    };
+    let test_mod = if integration_uses.is_some() {
+        None
+    } else {
+        Some(mod_tree.clone() + "::tests")
+    };
+    syntax_context.append_field_visibility(&mut context, crate_context, &test_mod);
+    syntax_context.append_constructor_hints(&mut context, crate_context, &test_mod);
+    syntax_context.append_doctests(&mut context, &fn_name);
+    let file_name = get_output_file_name(complete_function_name);
+    let rs_file_name = file_name.clone() + ".rs";
+    let output_file_path = output_path.join(&rs_file_name);
+    let mut file = File::create(output_file_path).unwrap();
+    file.write_all(context.as_bytes()).unwrap();
+
+    let signature = fn_type.get_signature();
+    let index_record = IndexRecord {
+        complete_name: complete_function_name.clone(),
+        kind: fn_type.get_kind(),
+        mod_path: mod_tree.clone(),
+        visibility: fn_type.get_visibility().get_label(),
+        signature: get_signature_string(&signature, &format!("fn {}", signature.ident)),
+        doc: get_doc_string(&fn_type.get_attrs()),
+        context_file: String::from("rfocxt/") + &rs_file_name,
+        location: crate_context.get_source_location(&fn_type.get_tokens()),
+        uses_ffi: !syntax_context.foreign_mods.is_empty(),
+        native_libs: syntax_context.get_native_libs(),
+    };
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(output_path.join("index.jsonl"))
+        .unwrap();
+    file.write_all((serde_json::to_string(&index_record).unwrap() + "\n").as_bytes())
+        .unwrap();
+
+    let mut test_file_name = None;
+    if let Some(test_scaffold) = get_test_scaffold(
+        fn_type,
+        mod_tree,
+        &crate_context.get_crate_name(),
+        &integration_uses,
+    ) {
+        let test_file = file_name.clone() + "_test.rs";
+        let mut file = File::create(output_path.join(&test_file)).unwrap();
+        file.write_all(test_scaffold.as_bytes()).unwrap();
+        test_file_name = Some(test_file);
+    }
+
+    let directory_path = output_path.join("new_callsandtypes");
+    create_dir_all(&directory_path).unwrap();
+    let file_path = PathBuf::from(&directory_path).join(format!("{}.json", file_name));
+    let mut file = File::create(&file_path).unwrap();
+    file.write_all(serde_json::to_string(&data).unwrap().as_bytes())
+        .unwrap();
+
+    let has_mir_outputs = !crate_context.get_config().get_syntax_only();
+    crate_context.insert_manifest_entry(
+        complete_function_name,
+        ManifestEntry {
+            context: rs_file_name,
+            test_scaffold: test_file_name,
+            callsandtypes: format!("callsandtypes/{}.json", file_name),
+            new_callsandtypes: format!("new_callsandtypes/{}.json", file_name),
+            basic_blocks: has_mir_outputs.then(|| format!("basic_blocks/{}.txt", file_name)),
+            branches: has_mir_outputs.then(|| format!("branches/{}.json", file_name)),
+            panics: has_mir_outputs.then(|| format!("panics/{}.json", file_name)),
+        },
+    );
+}
+
+// the calls and types of a fn from its syntax, a `#[test]` fn records the crate fns it calls
+fn get_syntax_data(
+    scope: &FnScope,
+    item_fn: &ItemFn,
+    fns: &BTreeMap<String, FnData>,
+    structs: &BTreeMap<String, StructData>,
+    fn_output_names: &BTreeMap<String, String>,
+    crate_context: &CrateContext,
+) -> CallsAndTypes {
+    let is_test = item_fn.attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "test")
+    });
+    let mut data = CallsAndTypes {
+        mod_name: scope.mod_tree.rsplit("::").next().unwrap().to_string(),
+        reachable: false,
+        doc: get_doc_string(&item_fn.attrs),
+        is_test,
+        test_source: String::new(),
+        test_calls: Vec::new(),
+        fn_source: crate_context.get_source_info(item_fn),
+        unsafe_info: UnsafeInfo::default(),
+        impl_calls: Vec::new(),
+        statics: Vec::new(),
+        consts: Vec::new(),
+        calls: Vec::new(),
+        types: Vec::new(),
+    };
+    let unsafe_blocks =
+        get_calls_and_types(scope, &item_fn.sig, &item_fn.block, fns, structs, &mut data);
+    for unsafe_block in unsafe_blocks.iter() {
+        if let Some(source_info) = crate_context.get_source_info(unsafe_block) {
+            data.unsafe_info.unsafe_blocks.push(source_info);
+        }
+    }
+    if is_test {
+        data.test_source = unparse(&syn::File {
+            shebang: None,
+            attrs: Vec::new(),
+            items: vec![Item::Fn(item_fn.clone())],
+        });
+        for call in data.calls.iter() {
+            if let Some(fn_output_name) = fn_output_names.get(call) {
+                data.test_calls.push(fn_output_name.clone());
+            }
+        }
+    }
+    data
+}
+
+fn write_syntax_data(
+    output_path: &std::path::Path,
+    complete_function_name: &str,
+    data: &CallsAndTypes,
+) {
+    let file_path = output_path.join(
+        String::from("callsandtypes/") + &get_output_file_name(complete_function_name) + ".json",
+    );
+    let mut file = File::create(file_path).unwrap();
+    file.write_all(serde_json::to_string(data).unwrap().as_bytes())
+        .unwrap();
+}
+
+fn expand_use_tree(
+    tree: &SynUseTree,
+    visibility: &MyVisibility,
+    expand_path: String,
+    expanded_trees: &mut Vec<UseTree>,
+) {
+    match tree {
+        SynUseTree::Path(use_path) => {
+            let mut path_str = String::new();
+            if expand_path != String::new() {
+                path_str = expand_path + "::" + &use_path.ident.to_string();
+            } else {
+                path_str = use_path.ident.to_string();
+            }
+            expand_use_tree(&use_path.tree, visibility, path_str, expanded_trees);
+        }
+        SynUseTree::Name(use_name) => {
+            let mut path_str = String::new();
+            if expand_path != String::new() {
+                path_str = expand_path + "::" + &use_name.ident.to_string();
+            } else {
+                path_str = use_name.ident.to_string();
+            }
+            let use_tree = UseTree::new(
+                use_name.ident.to_string(),
+                path_str,
+                None,
+                visibility.clone(),
+            );
+            expanded_trees.push(use_tree);
+        }
+        SynUseTree::Glob(_) => {
+            let mut path_str = String::new();
+            if expand_path != String::new() {
+                path_str = expand_path + "::*";
+            } else {
+                path_str = "*".to_string();
+            }
+            let use_tree = UseTree::new("*".to_string(), path_str, None, visibility.clone());
+            expanded_trees.push(use_tree);
+        }
+        SynUseTree::Rename(use_rename) => {
+            let mut path_str = String::new();
+            if expand_path != String::new() {
+                path_str = expand_path + "::" + &use_rename.ident.to_string();
+                let use_tree = UseTree::new(
                     use_rename.ident.to_string(),
                     path_str,
                     Some(use_rename.rename.to_string()),
@@ -716,6 +1852,8 @@ pub struct SyntaxContext {
     impls: Vec<ImplItem>,
     functions: Vec<FnItem>,
     traits: Vec<TraitItem>,
+    foreign_mods: Vec<ForeignModItem>,
+    macros: Vec<MacroItem>,
     use_trees: Vec<UseTree>,
 }
 
@@ -734,6 +1872,8 @@ impl SyntaxContext {
             impls: Vec::new(),
             functions: Vec::new(),
             traits: Vec::new(),
+            foreign_mods: Vec::new(),
+            macros: Vec::new(),
             use_trees: Vec::new(),
         }
     }
@@ -746,26 +1886,19 @@ impl SyntaxContext {
             match item {
                 Item::Const(item_const) => {
                     let mut const_item = ConstItem::new();
-                    let mut modified_item_const = item_const.clone();
-                    modified_item_const.attrs = delete_doc_attributes(&modified_item_const.attrs);
-                    const_item.insert_item(&modified_item_const);
+                    const_item.insert_item(item_const);
                     const_item.insert_visibility(parse_visibility(&item_const.vis));
                     syntax_context.consts.push(const_item);
                 }
                 Item::TraitAlias(item_trait_alias) => {
                     let mut trait_alias_item = TraitAliasItem::new();
-                    let mut modified_item_trait_alias = item_trait_alias.clone();
-                    modified_item_trait_alias.attrs =
-                        delete_doc_attributes(&modified_item_trait_alias.attrs);
-                    trait_alias_item.insert_item(&modified_item_trait_alias);
+                    trait_alias_item.insert_item(item_trait_alias);
                     trait_alias_item.insert_visibility(parse_visibility(&item_trait_alias.vis));
                     syntax_context.trait_aliases.push(trait_alias_item);
                 }
                 Item::Use(item_use) => {
                     let mut use_item = UseItem::new();
-                    let mut modified_item_use = item_use.clone();
-                    modified_item_use.attrs = delete_doc_attributes(&modified_item_use.attrs);
-                    use_item.insert_item(&modified_item_use);
+                    use_item.insert_item(item_use);
                     let visibility = parse_visibility(&item_use.vis);
                     use_item.insert_visibility(visibility.clone());
                     syntax_context.uses.push(use_item);
@@ -782,7 +1915,6 @@ impl SyntaxContext {
                     let mut mod_item = ModItem::new();
                     mod_item.insert_mod_name(&item_mod.ident.to_string());
                     let mut modified_item_mod = item_mod.clone();
-                    modified_item_mod.attrs = delete_doc_attributes(&modified_item_mod.attrs);
                     if let Some(content) = &mut modified_item_mod.content {
                         mod_item.insert_items(&content.1);
                         &content.1.clear();
@@ -812,41 +1944,33 @@ impl SyntaxContext {
                 }
                 Item::Static(item_static) => {
                     let mut static_item = StaticItem::new();
-                    let mut modified_item_static = item_static.clone();
-                    modified_item_static.attrs = delete_doc_attributes(&modified_item_static.attrs);
-                    static_item.insert_item(&modified_item_static);
+                    static_item.insert_item(item_static);
                     static_item.insert_visibility(parse_visibility(&item_static.vis));
                     syntax_context.statics.push(static_item);
                 }
                 Item::Type(item_type) => {
                     let mut type_item = TypeItem::new();
-                    let mut modified_item_type = item_type.clone();
-                    modified_item_type.attrs = delete_doc_attributes(&modified_item_type.attrs);
-                    type_item.insert_item(&modified_item_type);
+                    type_item.insert_item(item_type);
                     type_item.insert_visibility(parse_visibility(&item_type.vis));
                     syntax_context.types.push(type_item);
                 }
                 Item::Struct(item_struct) => {
                     let mut struct_item = StructItem::new();
                     struct_item.insert_struct_name(&item_struct.ident.to_string());
-                    let mut modified_item_struct = item_struct.clone();
-                    modified_item_struct.attrs = delete_doc_attributes(&modified_item_struct.attrs);
-                    struct_item.insert_item(&modified_item_struct);
+                    struct_item.insert_item(item_struct);
                     struct_item.insert_visibility(parse_visibility(&item_struct.vis));
                     let mut relative_types: Vec<String> = Vec::new();
-                    visit_fields(&modified_item_struct.fields, &mut relative_types);
+                    visit_fields(&item_struct.fields, &mut relative_types);
                     struct_item.insert_relative_types(relative_types);
                     syntax_context.structs.push(struct_item);
                 }
                 Item::Enum(item_enum) => {
                     let mut enum_item = EnumItem::new();
                     enum_item.insert_enum_name(&item_enum.ident.to_string());
-                    let mut modified_item_enum = item_enum.clone();
-                    modified_item_enum.attrs = delete_doc_attributes(&modified_item_enum.attrs);
-                    enum_item.insert_item(&modified_item_enum);
+                    enum_item.insert_item(item_enum);
                     enum_item.insert_visibility(parse_visibility(&item_enum.vis));
                     let mut relative_types: Vec<String> = Vec::new();
-                    for variant in modified_item_enum.variants.iter() {
+                    for variant in item_enum.variants.iter() {
                         visit_fields(&variant.fields, &mut relative_types);
                     }
                     enum_item.insert_relative_types(relative_types);
@@ -855,12 +1979,10 @@ impl SyntaxContext {
                 Item::Union(item_union) => {
                     let mut union_item = UnionItem::new();
                     union_item.insert_union_name(&item_union.ident.to_string());
-                    let mut modified_item_union = item_union.clone();
-                    modified_item_union.attrs = delete_doc_attributes(&modified_item_union.attrs);
-                    union_item.insert_item(&modified_item_union);
+                    union_item.insert_item(item_union);
                     union_item.insert_visibility(parse_visibility(&item_union.vis));
                     let mut relative_types: Vec<String> = Vec::new();
-                    visit_fields_named(&modified_item_union.fields, &mut relative_types);
+                    visit_fields_named(&item_union.fields, &mut relative_types);
                     union_item.insert_relative_types(relative_types);
                     syntax_context.unions.push(union_item);
                 }
@@ -870,7 +1992,6 @@ impl SyntaxContext {
                     impl_num += 1;
                     let mut modified_item_impl = item_impl.clone();
                     modified_item_impl.items = Vec::new();
-                    modified_item_impl.attrs = delete_doc_attributes(&modified_item_impl.attrs);
                     impl_item.insert_item(&modified_item_impl);
                     let mut struct_name = String::new();
                     let mut import_names: Vec<String> = Vec::new();
@@ -883,6 +2004,8 @@ impl SyntaxContext {
                     }
                     impl_item.insert_struct_name(&struct_name);
                     impl_item.insert_struct_import_name(&import_names.join("::"));
+                    impl_item
+                        .insert_self_type(SelfType::new(&item_impl.self_ty, &item_impl.generics));
                     let mut trait_name = String::new();
                     if item_impl.trait_.clone() != None {
                         trait_name = item_impl
@@ -905,21 +2028,15 @@ impl SyntaxContext {
                     for item in item_impl.items.iter() {
                         match item {
                             SynImplItem::Const(item_const) => {
-                                let mut modified_item_const = item_const.clone();
-                                modified_item_const.attrs =
-                                    delete_doc_attributes(&modified_item_const.attrs);
                                 let mut impl_const_item = ImplConstItem::new();
-                                impl_const_item.insert_item(&modified_item_const);
+                                impl_const_item.insert_item(item_const);
                                 impl_const_item
                                     .insert_visibility(parse_visibility(&item_const.vis));
                                 impl_item.insert_const(&impl_const_item);
                             }
                             SynImplItem::Type(item_type) => {
-                                let mut modified_item_type = item_type.clone();
-                                modified_item_type.attrs =
-                                    delete_doc_attributes(&modified_item_type.attrs);
                                 let mut impl_type_item = ImplTypeItem::new();
-                                impl_type_item.insert_item(&modified_item_type);
+                                impl_type_item.insert_item(item_type);
                                 impl_type_item.insert_visibility(parse_visibility(&item_type.vis));
                                 impl_item.insert_type(&impl_type_item);
                             }
@@ -928,10 +2045,7 @@ impl SyntaxContext {
                                 impl_fn_item.insert_fn_name(&item_fn.sig.ident.to_string());
                                 let prefix = format!("{{impl#{}}}", impl_item.get_impl_num());
                                 impl_fn_item.insert_complete_name_in_file(&prefix);
-                                let mut modified_item_fn = item_fn.clone();
-                                modified_item_fn.attrs =
-                                    delete_doc_attributes(&modified_item_fn.attrs);
-                                impl_fn_item.insert_item(&modified_item_fn);
+                                impl_fn_item.insert_item(item_fn);
                                 let mut inside_items: Vec<Item> = Vec::new();
                                 for stmt in item_fn.block.stmts.iter() {
                                     if let Stmt::Item(stmt_item) = stmt {
@@ -951,9 +2065,7 @@ impl SyntaxContext {
                     let mut fn_item = FnItem::new();
                     fn_item.insert_function_name(&item_fn.sig.ident.to_string());
                     fn_item.insert_complete_name_in_file(&String::new());
-                    let mut modified_item_fn = item_fn.clone();
-                    modified_item_fn.attrs = delete_doc_attributes(&modified_item_fn.attrs);
-                    fn_item.insert_item(&modified_item_fn);
+                    fn_item.insert_item(item_fn);
                     let mut inside_items: Vec<Item> = Vec::new();
                     for stmt in item_fn.block.stmts.iter() {
                         if let Stmt::Item(stmt_item) = stmt {
@@ -968,25 +2080,31 @@ impl SyntaxContext {
                     let mut trait_item = TraitItem::new();
                     trait_item.insert_trait_name(&item_trait.ident.to_string());
                     let mut modified_item_trait = item_trait.clone();
-                    modified_item_trait.attrs = delete_doc_attributes(&modified_item_trait.attrs);
                     modified_item_trait.items = Vec::new();
                     trait_item.insert_item(&modified_item_trait);
+                    for supertrait in item_trait.supertraits.iter() {
+                        if let TypeParamBound::Trait(trait_bound) = supertrait {
+                            let segments = &trait_bound.path.segments;
+                            let mut supertrait_name =
+                                Name::new(&segments.last().unwrap().ident.to_string());
+                            let import_names: Vec<String> = segments
+                                .iter()
+                                .map(|segment| segment.ident.to_string())
+                                .collect();
+                            supertrait_name.insert_import_name(&import_names.join("::"));
+                            trait_item.insert_supertrait(&supertrait_name);
+                        }
+                    }
                     for item in item_trait.items.iter() {
                         match item {
                             SynTraitItem::Const(item_const) => {
-                                let mut modified_item_const = item_const.clone();
-                                modified_item_const.attrs =
-                                    delete_doc_attributes(&modified_item_const.attrs);
                                 let mut trait_const_item = TraitConstItem::new();
-                                trait_const_item.insert_item(&modified_item_const);
+                                trait_const_item.insert_item(item_const);
                                 trait_item.insert_const(&trait_const_item);
                             }
                             SynTraitItem::Type(item_type) => {
-                                let mut modified_item_type = item_type.clone();
-                                modified_item_type.attrs =
-                                    delete_doc_attributes(&modified_item_type.attrs);
                                 let mut trait_type_item = TraitTypeItem::new();
-                                trait_type_item.insert_item(&modified_item_type);
+                                trait_type_item.insert_item(item_type);
                                 trait_item.insert_type(&trait_type_item);
                             }
                             SynTraitItem::Fn(item_fn) => {
@@ -994,10 +2112,7 @@ impl SyntaxContext {
                                 trait_fn_item.insert_fn_name(&item_fn.sig.ident.to_string());
                                 trait_fn_item
                                     .insert_complete_name_in_file(&trait_item.get_trait_name_str());
-                                let mut modified_item_fn = item_fn.clone();
-                                modified_item_fn.attrs =
-                                    delete_doc_attributes(&modified_item_fn.attrs);
-                                trait_fn_item.insert_item(&modified_item_fn);
+                                trait_fn_item.insert_item(item_fn);
                                 let mut inside_items: Vec<Item> = Vec::new();
                                 if let Some(block) = &item_fn.default {
                                     for stmt in block.stmts.iter() {
@@ -1016,6 +2131,39 @@ impl SyntaxContext {
                     trait_item.insert_item(&modified_item_trait);
                     syntax_context.traits.push(trait_item);
                 }
+                Item::ForeignMod(item_foreign_mod) => {
+                    let mut foreign_mod_item = ForeignModItem::new();
+                    let mut modified_item_foreign_mod = item_foreign_mod.clone();
+                    modified_item_foreign_mod.items = Vec::new();
+                    foreign_mod_item.insert_item(&modified_item_foreign_mod);
+                    for item in item_foreign_mod.items.iter() {
+                        match item {
+                            ForeignItem::Fn(item_fn) => {
+                                let mut foreign_fn_item = ForeignFnItem::new();
+                                foreign_fn_item.insert_fn_name(&item_fn.sig.ident.to_string());
+                                foreign_fn_item.insert_item(item_fn);
+                                foreign_fn_item.insert_visibility(parse_visibility(&item_fn.vis));
+                                foreign_mod_item.insert_function(&foreign_fn_item);
+                            }
+                            ForeignItem::Static(item_static) => {
+                                let mut foreign_static_item = ForeignStaticItem::new();
+                                foreign_static_item
+                                    .insert_static_name(&item_static.ident.to_string());
+                                foreign_static_item.insert_item(item_static);
+                                foreign_static_item
+                                    .insert_visibility(parse_visibility(&item_static.vis));
+                                foreign_mod_item.insert_static(&foreign_static_item);
+                            }
+                            _ => {}
+                        }
+                    }
+                    syntax_context.foreign_mods.push(foreign_mod_item);
+                }
+                Item::Macro(item_macro) => {
+                    let mut macro_item = MacroItem::new();
+                    macro_item.insert_item(item_macro);
+                    syntax_context.macros.push(macro_item);
+                }
                 _ => {}
             }
         }
@@ -1103,6 +2251,336 @@ impl SyntaxContext {
         for trait_item in self.traits.iter_mut() {
             trait_item.insert_parent_mod_tree(mod_tree);
         }
+        for foreign_mod_item in self.foreign_mods.iter_mut() {
+            foreign_mod_item.insert_parent_mod_tree(mod_tree);
+        }
+        for macro_item in self.macros.iter_mut() {
+            macro_item.insert_mod_tree(mod_tree);
+        }
+        for const_item in self.consts.iter_mut() {
+            const_item.insert_mod_tree(mod_tree);
+        }
+        for trait_alias_item in self.trait_aliases.iter_mut() {
+            trait_alias_item.insert_mod_tree(mod_tree);
+        }
+        for use_item in self.uses.iter_mut() {
+            use_item.insert_mod_tree(mod_tree);
+        }
+        for mod_item in self.mods.iter_mut() {
+            mod_item.insert_mod_tree(mod_tree);
+        }
+        for static_item in self.statics.iter_mut() {
+            static_item.insert_mod_tree(mod_tree);
+        }
+        for type_item in self.types.iter_mut() {
+            type_item.insert_mod_tree(mod_tree);
+        }
+    }
+
+    pub fn get_macro_items(&self, macro_items: &mut Vec<MacroItem>) {
+        macro_items.extend(self.macros.iter().cloned());
+    }
+
+    fn insert_macro(&mut self, macro_item: &MacroItem) {
+        if !self.macros.contains(macro_item) {
+            self.macros.push(macro_item.clone());
+        }
+    }
+
+    fn insert_trait(&mut self, trait_item: &TraitItem) {
+        let has_trait = self
+            .traits
+            .iter()
+            .any(|has_trait_item| has_trait_item.get_item().eq(trait_item.get_item()));
+        if !has_trait {
+            let mut trait_item = trait_item.clone();
+            trait_item.clear();
+            self.traits.push(trait_item);
+        }
+    }
+
+    fn get_item_count(&self) -> usize {
+        let mut count = self.traits.len() + self.impls.len();
+        for trait_item in self.traits.iter() {
+            count += trait_item.get_fns().len()
+                + trait_item.get_types().len()
+                + trait_item.get_consts().len();
+        }
+        for impl_item in self.impls.iter() {
+            count += impl_item.get_fns().len();
+        }
+        count
+    }
+
+    // a trait brings its supertraits, the fns the impls in the context implement, the fns its
+    // default bodies call and the associated types and consts that are used through `Self`
+    fn complete_traits(
+        &mut self,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
+        trait_default_methods: bool,
+    ) {
+        for impl_item in self.impls.clone().iter() {
+            if let Some(trait_name) = get_impl_trait_import_name(impl_item) {
+                if let Some(StructData {
+                    struct_type: StructType::Trait(trait_item),
+                    ..
+                }) = structs.get(&trait_name)
+                {
+                    self.insert_trait(trait_item);
+                }
+            }
+        }
+        // the fns wanted from each trait by its subtraits and by the impls of `dyn Trait`
+        let mut wanted_fns: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for impl_item in self.impls.iter() {
+            if impl_item.get_trait_name().is_some() {
+                continue;
+            }
+            if let Type::TraitObject(trait_object) = &*impl_item.get_item().self_ty {
+                let mut visitor = SelfReferenceVisitor::new();
+                for impl_fn_item in impl_item.get_fns().iter() {
+                    visitor.visit_impl_item_fn(&impl_fn_item.get_item());
+                }
+                for bound in trait_object.bounds.iter() {
+                    if let TypeParamBound::Trait(trait_bound) = bound {
+                        let bound_name =
+                            trait_bound.path.segments.last().unwrap().ident.to_string();
+                        for trait_item in self.traits.iter() {
+                            if trait_item.get_name().eq(&bound_name) {
+                                wanted_fns
+                                    .entry(
+                                        trait_item.get_trait_name().get_import_name().to_string(),
+                                    )
+                                    .or_default()
+                                    .extend(visitor.names.iter().cloned());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        loop {
+            let item_count = self.get_item_count();
+            let mut index = 0;
+            while index < self.traits.len() {
+                self.complete_trait(index, fns, structs, trait_default_methods, &mut wanted_fns);
+                index += 1;
+            }
+            if self.get_item_count() == item_count {
+                break;
+            }
+        }
+        // trait impls keep the associated items the trait in the context declares, inherent impls
+        // the ones their fns use and impls of foreign traits all of them
+        for impl_item in self.impls.iter_mut() {
+            match get_impl_trait_import_name(impl_item) {
+                Some(trait_name) => {
+                    let trait_item = self.traits.iter().find(|trait_item| {
+                        trait_item
+                            .get_trait_name()
+                            .get_import_name()
+                            .to_string()
+                            .eq(&trait_name)
+                    });
+                    if let Some(trait_item) = trait_item {
+                        let mut type_and_const_names: Vec<String> = trait_item
+                            .get_types()
+                            .iter()
+                            .map(|trait_type_item| trait_type_item.get_name())
+                            .collect();
+                        type_and_const_names.extend(
+                            trait_item
+                                .get_consts()
+                                .iter()
+                                .map(|trait_const_item| trait_const_item.get_name()),
+                        );
+                        impl_item.retain_types_and_consts(&type_and_const_names);
+                    }
+                }
+                None => {
+                    let mut visitor = SelfReferenceVisitor::new();
+                    for impl_fn_item in impl_item.get_fns().iter() {
+                        visitor.visit_impl_item_fn(&impl_fn_item.get_item());
+                    }
+                    impl_item.retain_types_and_consts(&visitor.names);
+                }
+            }
+        }
+    }
+
+    fn complete_trait(
+        &mut self,
+        trait_index: usize,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
+        trait_default_methods: bool,
+        wanted_fns: &mut BTreeMap<String, Vec<String>>,
+    ) {
+        let trait_name = self.traits[trait_index]
+            .get_trait_name()
+            .get_import_name()
+            .to_string();
+        let full_trait_item = match structs.get(&trait_name) {
+            Some(StructData {
+                struct_type: StructType::Trait(trait_item),
+                ..
+            }) => trait_item.clone(),
+            _ => return,
+        };
+        let is_trait_impl = |impl_item: &ImplItem| {
+            get_impl_trait_import_name(impl_item).is_some_and(|name| name.eq(&trait_name))
+        };
+        let mut supertrait_names: Vec<String> = Vec::new();
+        for supertrait in full_trait_item.get_supertraits().iter() {
+            let supertrait_name = supertrait.get_import_name().to_string();
+            if let Some(StructData {
+                struct_type: StructType::Trait(supertrait_item),
+                ..
+            }) = structs.get(&supertrait_name)
+            {
+                self.insert_trait(supertrait_item);
+                supertrait_names.push(supertrait_name);
+            }
+        }
+
+        // the impls of the supertraits for the same self types
+        let self_types: Vec<Type> = self
+            .impls
+            .iter()
+            .filter(|impl_item| is_trait_impl(impl_item))
+            .map(|impl_item| (*impl_item.get_item().self_ty).clone())
+            .collect();
+        for fn_data in fns.values() {
+            if let FnType::ImplFn(_, fn_impl_item) = &fn_data.fn_type {
+                let is_supertrait_impl = get_impl_trait_import_name(fn_impl_item)
+                    .is_some_and(|name| supertrait_names.contains(&name));
+                if is_supertrait_impl
+                    && self_types.contains(&fn_impl_item.get_item().self_ty)
+                    && !self
+                        .impls
+                        .iter()
+                        .any(|impl_item| impl_item.get_item().eq(fn_impl_item.get_item()))
+                {
+                    self.impls.push(fn_impl_item.clone());
+                }
+            }
+        }
+
+        // the fns of the trait, following the calls of the default bodies
+        let mut fn_names: Vec<String> = self.traits[trait_index]
+            .get_fns()
+            .iter()
+            .map(|trait_fn_item| trait_fn_item.get_name())
+            .collect();
+        if let Some(names) = wanted_fns.get(&trait_name) {
+            fn_names.extend(names.iter().cloned());
+        }
+        for impl_item in self
+            .impls
+            .iter()
+            .filter(|impl_item| is_trait_impl(impl_item))
+        {
+            for impl_fn_item in impl_item.get_fns().iter() {
+                fn_names.push(impl_fn_item.get_name());
+            }
+        }
+        if trait_default_methods {
+            for trait_fn_item in full_trait_item.get_fns().iter() {
+                if trait_fn_item.get_item().default.is_some() {
+                    fn_names.push(trait_fn_item.get_name());
+                }
+            }
+        }
+        let mut fn_index = 0;
+        while fn_index < fn_names.len() {
+            let fn_name = fn_names[fn_index].clone();
+            fn_index += 1;
+            let trait_fn_item = full_trait_item
+                .get_fns()
+                .iter()
+                .find(|trait_fn_item| trait_fn_item.get_name().eq(&fn_name));
+            if let Some(trait_fn_item) = trait_fn_item {
+                if !self.traits[trait_index].has_function(&fn_name) {
+                    self.traits[trait_index].insert_function(trait_fn_item);
+                }
+                let mut visitor = SelfReferenceVisitor::new();
+                visitor.visit_trait_item_fn(&trait_fn_item.get_item());
+                for name in visitor.names {
+                    if full_trait_item.has_function(&name) {
+                        if !fn_names.contains(&name) {
+                            fn_names.push(name);
+                        }
+                    } else {
+                        for supertrait_name in supertrait_names.iter() {
+                            let names = wanted_fns.entry(supertrait_name.clone()).or_default();
+                            if !names.contains(&name) {
+                                names.push(name.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // the impls implement every required fn the trait declares
+        let required_fn_names: Vec<String> = self.traits[trait_index]
+            .get_fns()
+            .iter()
+            .filter(|trait_fn_item| trait_fn_item.get_item().default.is_none())
+            .map(|trait_fn_item| trait_fn_item.get_name())
+            .collect();
+        for impl_item in self.impls.iter_mut() {
+            if !is_trait_impl(impl_item) {
+                continue;
+            }
+            for fn_data in fns.values() {
+                if let FnType::ImplFn(impl_fn_item, fn_impl_item) = &fn_data.fn_type {
+                    if fn_impl_item.get_item().eq(impl_item.get_item())
+                        && required_fn_names.contains(&impl_fn_item.get_name())
+                        && !impl_item.get_fns().contains(impl_fn_item)
+                    {
+                        impl_item.insert_function(impl_fn_item);
+                    }
+                }
+            }
+        }
+
+        // the associated types and consts used by the header, the fns and the impls
+        let mut visitor = SelfReferenceVisitor::new();
+        visitor.visit_item_trait(self.traits[trait_index].get_item());
+        for trait_fn_item in self.traits[trait_index].get_fns().iter() {
+            visitor.visit_trait_item_fn(&trait_fn_item.get_item());
+        }
+        for impl_item in self
+            .impls
+            .iter()
+            .filter(|impl_item| is_trait_impl(impl_item))
+        {
+            for impl_fn_item in impl_item.get_fns().iter() {
+                visitor.visit_impl_item_fn(&impl_fn_item.get_item());
+            }
+        }
+        let mut name_index = 0;
+        while name_index < visitor.names.len() {
+            let name = visitor.names[name_index].clone();
+            name_index += 1;
+            if self.traits[trait_index].has_type_or_const(&name) {
+                continue;
+            }
+            for trait_type_item in full_trait_item.get_types().iter() {
+                if trait_type_item.get_name().eq(&name) {
+                    self.traits[trait_index].insert_type(trait_type_item);
+                    visitor.visit_trait_item_type(&trait_type_item.get_item());
+                }
+            }
+            for trait_const_item in full_trait_item.get_consts().iter() {
+                if trait_const_item.get_name().eq(&name) {
+                    self.traits[trait_index].insert_const(trait_const_item);
+                    visitor.visit_trait_item_const(&trait_const_item.get_item());
+                }
+            }
+        }
     }
 
     pub fn change_use_trees(&mut self, mod_context: &Rc<RefCell<ModContext>>) {
@@ -1161,6 +2639,8 @@ impl SyntaxContext {
 
     pub fn change_impl_name(&mut self, mod_context: &Rc<RefCell<ModContext>>) {
         for impl_item in self.impls.iter_mut() {
+            impl_item.insert_mod_tree(&mod_context.borrow().get_mod_tree());
+            impl_item.change_self_type_names(mod_context);
             let mut name = impl_item.get_struct_name().clone();
             name.change_name_for_impl_struct_name(mod_context);
             impl_item.change_struct_name(&name);
@@ -1174,6 +2654,7 @@ impl SyntaxContext {
         }
         for trait_item in self.traits.iter_mut() {
             trait_item.change_function_name();
+            trait_item.change_supertrait_names(mod_context);
         }
     }
 
@@ -1241,8 +2722,9 @@ impl SyntaxContext {
 
     pub fn get_result(
         &self,
-        fns: &mut HashMap<String, FnData>,
-        structs: &mut HashMap<String, StructData>,
+        mod_tree: &str,
+        fns: &mut BTreeMap<String, FnData>,
+        structs: &mut BTreeMap<String, StructData>,
     ) {
         for function_item in self.functions.iter() {
             let fn_data = FnData {
@@ -1254,7 +2736,7 @@ impl SyntaxContext {
         }
         for impl_item in self.impls.iter() {
             let mut empty_impl_item = impl_item.clone();
-            empty_impl_item.clear();
+            empty_impl_item.clear_fns();
             for function_item in impl_item.get_fns().iter() {
                 let fn_data = FnData {
                     fn_name: function_item.get_name(),
@@ -1278,7 +2760,7 @@ impl SyntaxContext {
             let struct_data = StructData {
                 struct_name: trait_item.get_name(),
                 complete_struct_name: trait_item.get_trait_name().get_import_name().to_string(),
-                struct_type: StructType::Trait(empty_trait_item),
+                struct_type: StructType::Trait(trait_item.clone()),
             };
             structs.insert(struct_data.complete_struct_name.clone(), struct_data);
         }
@@ -1306,42 +2788,689 @@ impl SyntaxContext {
             };
             structs.insert(union_data.complete_struct_name.clone(), union_data);
         }
+        for static_item in self.statics.iter() {
+            let static_name = static_item.get_item().ident.to_string();
+            let static_data = StructData {
+                struct_name: static_name.clone(),
+                complete_struct_name: format!("{}::{}", mod_tree, static_name),
+                struct_type: StructType::Static(static_item.clone()),
+            };
+            structs.insert(static_data.complete_struct_name.clone(), static_data);
+        }
+        for const_item in self.consts.iter() {
+            let const_name = const_item.get_item().ident.to_string();
+            let const_data = StructData {
+                struct_name: const_name.clone(),
+                complete_struct_name: format!("{}::{}", mod_tree, const_name),
+                struct_type: StructType::Const(const_item.clone()),
+            };
+            structs.insert(const_data.complete_struct_name.clone(), const_data);
+        }
+        for foreign_mod_item in self.foreign_mods.iter() {
+            let mut empty_foreign_mod_item = foreign_mod_item.clone();
+            empty_foreign_mod_item.clear();
+            for function_item in foreign_mod_item.get_fns().iter() {
+                let fn_data = FnData {
+                    fn_name: function_item.get_name(),
+                    complete_fn_name: function_item.get_complete_name(),
+                    fn_type: FnType::ForeignFn(
+                        function_item.clone(),
+                        empty_foreign_mod_item.clone(),
+                    ),
+                };
+                fns.insert(fn_data.complete_fn_name.clone(), fn_data);
+            }
+            for static_item in foreign_mod_item.get_statics().iter() {
+                let static_data = StructData {
+                    struct_name: static_item.get_name(),
+                    complete_struct_name: static_item.get_complete_name(),
+                    struct_type: StructType::ForeignStatic(
+                        static_item.clone(),
+                        empty_foreign_mod_item.clone(),
+                    ),
+                };
+                structs.insert(static_data.complete_struct_name.clone(), static_data);
+            }
+        }
     }
 
-    pub fn get_relative_types_for_struct(&self, name: &String, relative_types: &mut Vec<String>) {
-        for struct_item in self.structs.iter() {
-            if struct_item
+    pub fn get_impls_for_struct(&self, name: &String, impls: &mut Vec<ImplItem>) {
+        for impl_item in self.impls.iter() {
+            if impl_item
                 .get_struct_name()
                 .get_import_name()
                 .to_string()
                 .eq(name)
             {
-                *relative_types = struct_item.get_relative_types();
-                return;
+                impls.push(impl_item.clone());
             }
         }
+    }
+
+    fn get_type_names(&self) -> Vec<String> {
+        let mut type_names: Vec<String> = Vec::new();
+        for struct_item in self.structs.iter() {
+            type_names.push(struct_item.get_struct_name().get_import_name().to_string());
+        }
         for enum_item in self.enums.iter() {
-            if enum_item
-                .get_enum_name()
+            type_names.push(enum_item.get_enum_name().get_import_name().to_string());
+        }
+        for union_item in self.unions.iter() {
+            type_names.push(union_item.get_union_name().get_import_name().to_string());
+        }
+        type_names
+    }
+
+    // the fns, types and consts the trait in the context declares
+    fn get_trait_member_names(&self, trait_name: &str) -> Option<Vec<String>> {
+        let trait_item = self.traits.iter().find(|trait_item| {
+            trait_item
+                .get_trait_name()
                 .get_import_name()
                 .to_string()
-                .eq(name)
-            {
-                *relative_types = enum_item.get_relative_types();
-                return;
+                .eq(trait_name)
+        })?;
+        let mut member_names: Vec<String> = trait_item
+            .get_fns()
+            .iter()
+            .map(|trait_fn_item| trait_fn_item.get_name())
+            .collect();
+        member_names.extend(
+            trait_item
+                .get_types()
+                .iter()
+                .map(|trait_type_item| trait_type_item.get_name()),
+        );
+        member_names.extend(
+            trait_item
+                .get_consts()
+                .iter()
+                .map(|trait_const_item| trait_const_item.get_name()),
+        );
+        Some(member_names)
+    }
+
+    fn add_impls_of_types(&mut self, crate_context: &CrateContext) {
+        let crate_prefix = crate_context.get_crate_name() + "::";
+        for type_name in self.get_type_names().iter() {
+            let mut impls: Vec<ImplItem> = Vec::new();
+            crate_context.get_impls_for_struct(type_name, &mut impls);
+            for impl_item in impls.iter() {
+                let mut signature_impl_item = impl_item.clone();
+                signature_impl_item.clear_fn_stmts();
+                // an impl of a crate trait comes with the trait, and has only what it declares
+                if let Some(trait_name) = get_impl_trait_import_name(impl_item) {
+                    if trait_name.starts_with(&crate_prefix) {
+                        match self.get_trait_member_names(&trait_name) {
+                            Some(member_names) => {
+                                signature_impl_item.retain_fns(&member_names);
+                                signature_impl_item.retain_types_and_consts(&member_names);
+                            }
+                            None => continue,
+                        }
+                    }
+                }
+                let mut has_impl = false;
+                for has_impl_item in self.impls.iter_mut() {
+                    if !has_impl_item.get_item().eq(signature_impl_item.get_item()) {
+                        continue;
+                    }
+                    has_impl = true;
+                    for impl_type_item in signature_impl_item.get_types().iter() {
+                        if !has_impl_item.get_types().iter().any(|has_type_item| {
+                            has_type_item.get_item().eq(&impl_type_item.get_item())
+                        }) {
+                            has_impl_item.insert_type(impl_type_item);
+                        }
+                    }
+                    for impl_const_item in signature_impl_item.get_consts().iter() {
+                        if !has_impl_item.get_consts().iter().any(|has_const_item| {
+                            has_const_item.get_item().eq(&impl_const_item.get_item())
+                        }) {
+                            has_impl_item.insert_const(impl_const_item);
+                        }
+                    }
+                    for impl_fn_item in signature_impl_item.get_fns().iter() {
+                        if !has_impl_item
+                            .get_fns()
+                            .iter()
+                            .any(|has_fn_item| has_fn_item.get_name().eq(&impl_fn_item.get_name()))
+                        {
+                            has_impl_item.insert_function(impl_fn_item);
+                        }
+                    }
+                }
+                if !has_impl {
+                    self.impls.push(signature_impl_item);
+                }
+            }
+        }
+    }
+
+    fn append_tests(&self, out: &mut String, fn_name: &str, tests: &[String]) {
+        if tests.is_empty() {
+            return;
+        }
+        // the tests are kept out of the build of the context like in their own crate
+        *out += &format!("\n// Existing tests that call `{}`:\n", fn_name);
+        *out += "#[cfg(test)]\nmod existing_tests {\n    use super::*;\n";
+        for test in tests.iter() {
+            *out += "\n";
+            *out += &indent(test.trim_end());
+            *out += "\n";
+        }
+        *out += "}\n";
+    }
+
+    fn append_panic_sites(&self, out: &mut String, fn_name: &str, panic_sites: &[PanicSite]) {
+        if panic_sites.is_empty() {
+            return;
+        }
+        *out += &format!("\n// Panic sites of `{}`:\n", fn_name);
+        for panic_site in panic_sites.iter() {
+            let code = panic_site
+                .code
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ");
+            *out += &format!(
+                "//   {}:{} {}: `{}`",
+                panic_site.loc.get_file(),
+                panic_site.loc.get_startline(),
+                panic_site.message,
+                code
+            );
+            if let Some(cond) = &panic_site.cond {
+                let cond = cond.split_whitespace().collect::<Vec<&str>>().join(" ");
+                *out += &format!(", panics unless `{}`", cond);
+            }
+            *out += "\n";
+        }
+    }
+
+    fn append_unsafe_info(&self, out: &mut String, fn_name: &str, unsafe_info: &UnsafeInfo) {
+        if !unsafe_info.has_unsafe() {
+            return;
+        }
+        *out += &format!("\n// Unsafe code in `{}`:\n", fn_name);
+        if unsafe_info.is_unsafe_fn {
+            *out += "//   it is an `unsafe fn`\n";
+        }
+        for unsafe_block in unsafe_info.unsafe_blocks.iter() {
+            *out += &format!(
+                "//   unsafe block at {}:{}\n",
+                unsafe_block.get_file(),
+                unsafe_block.get_startline()
+            );
+        }
+        if unsafe_info.derefs_raw_ptr {
+            *out += "//   it dereferences raw pointers\n";
+        }
+        for unsafe_call in unsafe_info.unsafe_calls.iter() {
+            *out += &format!(
+                "//   calls the unsafe function `{}` at {}:{}\n",
+                unsafe_call.callee,
+                unsafe_call.loc.get_file(),
+                unsafe_call.loc.get_startline()
+            );
+            for line in unsafe_call.safety_doc.lines() {
+                if line.is_empty() {
+                    *out += "//\n";
+                } else {
+                    *out += &format!("//     {}\n", line);
+                }
+            }
+        }
+    }
+
+    fn append_foreign_items(&self, out: &mut String, fn_name: &str) {
+        if self.foreign_mods.is_empty() {
+            return;
+        }
+        *out += &format!(
+            "\n// Foreign items used by `{}`, a native library must be linked:\n",
+            fn_name
+        );
+        for foreign_mod_item in self.foreign_mods.iter() {
+            let links = foreign_mod_item.get_links();
+            if links.is_empty() {
+                *out += &format!(
+                    "//   extern \"{}\" block in `{}`, no `#[link]` attribute:\n",
+                    foreign_mod_item.get_abi(),
+                    foreign_mod_item.get_mod_tree().to_string()
+                );
+            } else {
+                *out += &format!(
+                    "//   extern \"{}\" block in `{}`, linked with {}:\n",
+                    foreign_mod_item.get_abi(),
+                    foreign_mod_item.get_mod_tree().to_string(),
+                    links
+                        .iter()
+                        .map(|link| format!("`{}`", link))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+            }
+            for function_item in foreign_mod_item.get_fns().iter() {
+                *out += &format!("//     fn `{}`\n", function_item.get_complete_name());
+            }
+            for static_item in foreign_mod_item.get_statics().iter() {
+                *out += &format!("//     static `{}`\n", static_item.get_complete_name());
+            }
+        }
+    }
+
+    // the `#[link]` names of the foreign items in the context
+    fn get_native_libs(&self) -> Vec<String> {
+        let mut native_libs: Vec<String> = Vec::new();
+        for foreign_mod_item in self.foreign_mods.iter() {
+            for link in foreign_mod_item.get_links() {
+                if !native_libs.contains(&link) {
+                    native_libs.push(link);
+                }
+            }
+        }
+        native_libs
+    }
+
+    fn append_trait_summary(&self, out: &mut String, crate_context: &CrateContext) {
+        let trait_summary = self.get_trait_summary(crate_context);
+        if trait_summary.is_empty() {
+            return;
+        }
+        *out += "\n// Traits of context types:\n";
+        for line in trait_summary.iter() {
+            *out += &format!("// {}\n", line);
+        }
+    }
+
+    fn append_field_visibility(
+        &self,
+        out: &mut String,
+        crate_context: &CrateContext,
+        test_mod: &Option<String>,
+    ) {
+        let field_visibility = self.get_field_visibility(&crate_context.get_crate_name(), test_mod);
+        if field_visibility.is_empty() {
+            return;
+        }
+        match test_mod {
+            Some(test_mod) => *out += &format!("\n// Field visibility from `{}`:\n", test_mod),
+            None => *out += "\n// Field visibility from integration tests:\n",
+        }
+        for (type_label, lines) in field_visibility.iter() {
+            *out += &format!("//   {}:\n", type_label);
+            for line in lines.iter() {
+                *out += &format!("//     {}\n", line);
+            }
+        }
+    }
+
+    fn append_constructor_hints(
+        &self,
+        out: &mut String,
+        crate_context: &CrateContext,
+        test_mod: &Option<String>,
+    ) {
+        let constructor_hints = self.get_constructor_hints(crate_context, test_mod);
+        if constructor_hints.is_empty() {
+            return;
+        }
+        *out += "\n// How to construct the context types:\n";
+        for (type_name, type_hints) in constructor_hints.iter() {
+            *out += &format!("//   `{}`:\n", type_name);
+            for hint in type_hints.iter() {
+                *out += &format!("//     {}\n", hint);
             }
         }
+    }
+
+    fn append_doctests(&self, out: &mut String, fn_name: &str) {
+        let mut doctests: Vec<(String, Doctest)> = Vec::new();
+        self.get_doctests(&mut doctests);
+        if doctests.is_empty() {
+            return;
+        }
+        // examples of the focal function come first
+        doctests.sort_by_key(|(name, _)| !name.eq(fn_name));
+        *out += "\n// Doctest examples:\n";
+        for (name, doctest) in doctests.iter() {
+            let marks = doctest.get_marks();
+            if marks.is_empty() {
+                *out += &format!("//\n// From `{}`:\n", name);
+            } else {
+                *out += &format!("//\n// From `{}` ({}):\n", name, marks.join(", "));
+            }
+            for line in doctest.get_code().lines() {
+                if line.is_empty() {
+                    *out += "//\n";
+                } else {
+                    *out += &format!("// {}\n", line);
+                }
+            }
+        }
+    }
+
+    // per-field visibility of the types in the context as seen from the test module
+    fn get_field_visibility(
+        &self,
+        crate_name: &str,
+        test_mod: &Option<String>,
+    ) -> Vec<(String, Vec<String>)> {
+        let mut type_items: Vec<(String, Item)> = Vec::new();
+        for struct_item in self.structs.iter() {
+            type_items.push((
+                struct_item.get_struct_name().get_import_name().to_string(),
+                struct_item.to_item(),
+            ));
+        }
+        for enum_item in self.enums.iter() {
+            type_items.push((
+                enum_item.get_enum_name().get_import_name().to_string(),
+                enum_item.to_item(),
+            ));
+        }
         for union_item in self.unions.iter() {
-            if union_item
-                .get_union_name()
-                .get_import_name()
-                .to_string()
-                .eq(name)
-            {
-                *relative_types = union_item.get_relative_types();
-                return;
+            type_items.push((
+                union_item.get_union_name().get_import_name().to_string(),
+                union_item.to_item(),
+            ));
+        }
+        let mut annotations: Vec<(String, Vec<String>)> = Vec::new();
+        for (type_name, item) in type_items.iter() {
+            let def_mod = get_parent_mod(type_name);
+            let mut lines: Vec<String> = Vec::new();
+            let mut fields: Vec<&Field> = Vec::new();
+            let non_exhaustive = is_non_exhaustive(&get_item_attrs(item));
+            match item {
+                Item::Struct(item_struct) => fields.extend(item_struct.fields.iter()),
+                Item::Union(item_union) => fields.extend(item_union.fields.named.iter()),
+                Item::Enum(item_enum) => {
+                    for variant in item_enum.variants.iter() {
+                        if is_non_exhaustive(&variant.attrs) {
+                            lines.push(format!("variant `{}` is non_exhaustive", variant.ident));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            let mut all_visible = true;
+            for (i, field) in fields.iter().enumerate() {
+                let visibility = parse_visibility(&field.vis);
+                let visible = is_visible_from(&visibility, &def_mod, crate_name, test_mod);
+                all_visible = all_visible && visible;
+                let field_name = match &field.ident {
+                    Some(ident) => ident.to_string(),
+                    None => i.to_string(),
+                };
+                lines.push(format!(
+                    "{}: {}, {}",
+                    field_name,
+                    visibility.get_label(),
+                    if visible { "visible" } else { "not visible" }
+                ));
+            }
+            if let Item::Struct(_) = item {
+                // `#[non_exhaustive]` only forbids struct literals outside of the crate
+                if all_visible && !(non_exhaustive && test_mod.is_none()) {
+                    lines.insert(0, "a struct literal can be written".to_string());
+                } else {
+                    lines.insert(0, "a struct literal can not be written".to_string());
+                }
+            }
+            let mut type_label = format!("`{}`", type_name);
+            if non_exhaustive {
+                type_label += " (non_exhaustive)";
+            }
+            annotations.push((type_label, lines));
+        }
+        annotations
+    }
+
+    // the ways to get a value of a type from the impls and fns of the mod, as (rank, number
+    // of parameters, hint) by the complete name of the type
+    pub fn get_constructors(
+        &self,
+        mod_tree: &str,
+        crate_name: &str,
+        structs: &BTreeMap<String, StructData>,
+        constructors: &mut BTreeMap<String, Vec<(usize, usize, String)>>,
+    ) {
+        let mut scope = FnScope {
+            crate_name,
+            mod_tree,
+            use_trees: &self.use_trees,
+            self_type: None,
+            self_type_names: Vec::new(),
+            trait_name: None,
+        };
+        for impl_item in self.impls.iter() {
+            let item_impl = impl_item.get_item();
+            let impl_type_name = impl_item.get_struct_name().get_import_name().to_string();
+            let impl_name = impl_item.get_struct_name().get_name();
+            if let Some((_, trait_path, _)) = &item_impl.trait_ {
+                let trait_segment = trait_path.segments.last().unwrap();
+                let mut trait_arg = String::new();
+                if let PathArguments::AngleBracketed(arguments) = &trait_segment.arguments {
+                    if let Some(GenericArgument::Type(ty)) = arguments.args.first() {
+                        trait_arg = get_type_string(ty);
+                    }
+                }
+                let hint = match trait_segment.ident.to_string().as_str() {
+                    "Default" => Some((1, 0, format!("{}::default()", impl_name))),
+                    "From" => Some((2, 1, format!("{}::from(value: {})", impl_name, trait_arg))),
+                    "TryFrom" => Some((
+                        2,
+                        1,
+                        format!("{}::try_from(value: {})", impl_name, trait_arg),
+                    )),
+                    "FromStr" => Some((2, 1, format!("\"...\".parse::<{}>()", impl_name))),
+                    _ => None,
+                };
+                if let Some(hint) = hint {
+                    constructors
+                        .entry(impl_type_name.clone())
+                        .or_default()
+                        .push(hint);
+                }
+            }
+            scope.self_type = (!impl_type_name.is_empty()).then(|| impl_type_name.clone());
+            for impl_fn_item in impl_item.get_fns().iter() {
+                let sig = impl_fn_item.get_item().sig;
+                let type_name = match get_returned_type_name(&sig.output, &scope, structs) {
+                    Some(type_name) => type_name,
+                    None => continue,
+                };
+                let is_own_impl = type_name.eq(&impl_type_name);
+                // the trait hints above stand for the fns of trait impls of the type itself
+                if is_own_impl && item_impl.trait_.is_some() {
+                    continue;
+                }
+                let has_receiver = matches!(sig.inputs.first(), Some(FnArg::Receiver(_)));
+                let rank = if is_own_impl && !has_receiver { 1 } else { 3 };
+                constructors.entry(type_name).or_default().push((
+                    rank,
+                    sig.inputs.len(),
+                    get_signature_string(&sig, &format!("{}::{}", impl_name, sig.ident)),
+                ));
+            }
+        }
+        scope.self_type = None;
+        for fn_item in self.functions.iter() {
+            let sig = fn_item.get_item().sig;
+            if let Some(type_name) = get_returned_type_name(&sig.output, &scope, structs) {
+                constructors.entry(type_name).or_default().push((
+                    3,
+                    sig.inputs.len(),
+                    get_signature_string(&sig, &fn_item.get_complete_name()),
+                ));
+            }
+        }
+    }
+
+    // ranked ways to get a value of each type in the context: struct literals and enum
+    // variants, associated functions returning `Self`, `Default`, conversions, builders and
+    // other functions returning the type
+    fn get_constructor_hints(
+        &self,
+        crate_context: &CrateContext,
+        test_mod: &Option<String>,
+    ) -> Vec<(String, Vec<String>)> {
+        let mut type_items: Vec<(String, String, Item)> = Vec::new();
+        for struct_item in self.structs.iter() {
+            type_items.push((
+                struct_item.get_struct_name().get_import_name().to_string(),
+                struct_item.get_name(),
+                struct_item.to_item(),
+            ));
+        }
+        for enum_item in self.enums.iter() {
+            type_items.push((
+                enum_item.get_enum_name().get_import_name().to_string(),
+                enum_item.get_name(),
+                enum_item.to_item(),
+            ));
+        }
+        for union_item in self.unions.iter() {
+            type_items.push((
+                union_item.get_union_name().get_import_name().to_string(),
+                union_item.get_name(),
+                union_item.to_item(),
+            ));
+        }
+        let mut hints: Vec<(String, Vec<String>)> = Vec::new();
+        for (type_name, name, item) in type_items.iter() {
+            // candidates are (rank, number of parameters, hint)
+            let mut candidates: Vec<(usize, usize, String)> = Vec::new();
+            match item {
+                Item::Struct(item_struct) => {
+                    let def_mod = get_parent_mod(type_name);
+                    let all_fields_visible = item_struct.fields.iter().all(|field| {
+                        is_visible_from(
+                            &parse_visibility(&field.vis),
+                            &def_mod,
+                            &crate_context.get_crate_name(),
+                            test_mod,
+                        )
+                    });
+                    if all_fields_visible
+                        && !(is_non_exhaustive(&item_struct.attrs) && test_mod.is_none())
+                    {
+                        candidates.push((
+                            0,
+                            item_struct.fields.len(),
+                            format!("{}{}", name, get_fields_string(&item_struct.fields)),
+                        ));
+                    }
+                    if has_derive(&item_struct.attrs, "Default") {
+                        candidates.push((1, 0, format!("{}::default()", name)));
+                    }
+                }
+                Item::Enum(item_enum) => {
+                    if has_derive(&item_enum.attrs, "Default") {
+                        candidates.push((1, 0, format!("{}::default()", name)));
+                    }
+                    for variant in item_enum.variants.iter() {
+                        if is_non_exhaustive(&variant.attrs) && test_mod.is_none() {
+                            continue;
+                        }
+                        candidates.push((
+                            0,
+                            variant.fields.len(),
+                            format!(
+                                "{}::{}{}",
+                                name,
+                                variant.ident,
+                                get_fields_string(&variant.fields)
+                            ),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+            candidates.extend(crate_context.get_constructors(type_name));
+            candidates.sort_by_key(|(rank, param_num, _)| (*rank, *param_num));
+            let mut type_hints: Vec<String> = Vec::new();
+            for (_, _, hint) in candidates.iter() {
+                if type_hints.len() >= MAX_CONSTRUCTOR_HINTS {
+                    break;
+                }
+                if !type_hints.contains(hint) {
+                    type_hints.push(hint.clone());
+                }
+            }
+            if !type_hints.is_empty() {
+                hints.push((type_name.clone(), type_hints));
             }
         }
+        hints
+    }
+
+    fn get_trait_summary(&self, crate_context: &CrateContext) -> Vec<String> {
+        let mut summary: Vec<String> = Vec::new();
+        let mut type_items: Vec<(String, Item)> = Vec::new();
+        for struct_item in self.structs.iter() {
+            type_items.push((
+                struct_item.get_struct_name().get_import_name().to_string(),
+                struct_item.to_item(),
+            ));
+        }
+        for enum_item in self.enums.iter() {
+            type_items.push((
+                enum_item.get_enum_name().get_import_name().to_string(),
+                enum_item.to_item(),
+            ));
+        }
+        for union_item in self.unions.iter() {
+            type_items.push((
+                union_item.get_union_name().get_import_name().to_string(),
+                union_item.to_item(),
+            ));
+        }
+        for (type_name, item) in type_items.iter() {
+            let mut derives: Vec<String> = Vec::new();
+            for attr in get_item_attrs(item).iter() {
+                if !attr.path().is_ident("derive") {
+                    continue;
+                }
+                if let Ok(paths) =
+                    attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                {
+                    for path in paths.iter() {
+                        derives.push(path.segments.last().unwrap().ident.to_string());
+                    }
+                }
+            }
+            let mut impls: Vec<ImplItem> = Vec::new();
+            crate_context.get_impls_for_struct(type_name, &mut impls);
+            let mut traits: Vec<String> = Vec::new();
+            for impl_item in impls.iter() {
+                if let Some(trait_name) = impl_item.get_trait_name() {
+                    let mut trait_name = trait_name.get_import_name().to_string();
+                    // traits of the prelude, like `Default` or `From`, have no import name
+                    if trait_name.is_empty() {
+                        if let Some((_, trait_path, _)) = &impl_item.get_item().trait_ {
+                            trait_name = trait_path
+                                .segments
+                                .iter()
+                                .map(|segment| segment.ident.to_string())
+                                .collect::<Vec<String>>()
+                                .join("::");
+                        }
+                    }
+                    if !traits.contains(&trait_name) {
+                        traits.push(trait_name);
+                    }
+                }
+            }
+            if !derives.is_empty() {
+                summary.push(format!("`{}` derives: {}", type_name, derives.join(", ")));
+            }
+            if !traits.is_empty() {
+                summary.push(format!("`{}` implements: {}", type_name, traits.join(", ")));
+            }
+        }
+        summary
     }
 
     // pub fn get_item(&self, item_name: &String) -> SyntaxContext {
@@ -1546,20 +3675,168 @@ impl SyntaxContext {
     //     traits
     // }
 
+    pub fn get_fn_output_names(
+        &self,
+        mod_tree: &str,
+        fn_output_names: &mut BTreeMap<String, String>,
+    ) {
+        for function_item in self.functions.iter() {
+            fn_output_names.insert(
+                function_item.get_complete_name(),
+                mod_tree.to_string() + "::" + &function_item.get_complete_function_name_in_file(),
+            );
+        }
+        for impl_item in self.impls.iter() {
+            for function_item in impl_item.get_fns().iter() {
+                fn_output_names.insert(
+                    function_item.get_complete_name(),
+                    mod_tree.to_string()
+                        + "::"
+                        + &function_item.get_complete_function_name_in_file(),
+                );
+            }
+        }
+        for trait_item in self.traits.iter() {
+            for function_item in trait_item.get_fns().iter() {
+                fn_output_names.insert(
+                    function_item.get_complete_name(),
+                    mod_tree.to_string()
+                        + "::"
+                        + &function_item.get_complete_function_name_in_file(),
+                );
+            }
+        }
+    }
+
+    pub fn write_syntax_calls(
+        &self,
+        output_path: &std::path::Path,
+        mod_tree: &String,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
+        fn_output_names: &BTreeMap<String, String>,
+        crate_context: &CrateContext,
+    ) {
+        let crate_name = crate_context.get_crate_name();
+        let is_mod_reachable = crate_context.is_mod_reachable(mod_tree);
+        let mut scope = FnScope {
+            crate_name: &crate_name,
+            mod_tree,
+            use_trees: &self.use_trees,
+            self_type: None,
+            self_type_names: Vec::new(),
+            trait_name: None,
+        };
+        for function_item in self.functions.iter() {
+            let mut data = get_syntax_data(
+                &scope,
+                &function_item.get_item(),
+                fns,
+                structs,
+                fn_output_names,
+                crate_context,
+            );
+            data.reachable =
+                is_mod_reachable && matches!(function_item.get_visibility(), MyVisibility::PubT);
+            write_syntax_data(
+                output_path,
+                &(mod_tree.clone() + "::" + &function_item.get_complete_function_name_in_file()),
+                &data,
+            );
+        }
+        for impl_item in self.impls.iter() {
+            let self_type = impl_item.get_struct_name().get_import_name().to_string();
+            scope.self_type = (!self_type.is_empty()).then_some(self_type);
+            scope.self_type_names.clear();
+            impl_item
+                .get_self_type()
+                .get_path_names(&mut scope.self_type_names);
+            scope.trait_name = impl_item
+                .get_trait_name()
+                .as_ref()
+                .map(|trait_name| trait_name.get_import_name().to_string());
+            for function_item in impl_item.get_fns().iter() {
+                let impl_item_fn = function_item.get_item();
+                let item_fn = ItemFn {
+                    attrs: impl_item_fn.attrs,
+                    vis: impl_item_fn.vis,
+                    sig: impl_item_fn.sig,
+                    block: Box::new(impl_item_fn.block),
+                };
+                let mut data = get_syntax_data(
+                    &scope,
+                    &item_fn,
+                    fns,
+                    structs,
+                    fn_output_names,
+                    crate_context,
+                );
+                data.reachable = is_mod_reachable
+                    && (scope.trait_name.is_some()
+                        || matches!(function_item.get_visibility(), MyVisibility::PubT));
+                write_syntax_data(
+                    output_path,
+                    &(mod_tree.clone()
+                        + "::"
+                        + &function_item.get_complete_function_name_in_file()),
+                    &data,
+                );
+            }
+        }
+        scope.self_type = None;
+        scope.self_type_names.clear();
+        for trait_item in self.traits.iter() {
+            scope.trait_name = Some(trait_item.get_trait_name().get_import_name().to_string());
+            for function_item in trait_item.get_fns().iter() {
+                let trait_item_fn = function_item.get_item();
+                let block = match trait_item_fn.default {
+                    Some(block) => block,
+                    None => continue,
+                };
+                let item_fn = ItemFn {
+                    attrs: trait_item_fn.attrs,
+                    vis: Visibility::Inherited,
+                    sig: trait_item_fn.sig,
+                    block: Box::new(block),
+                };
+                let mut data = get_syntax_data(
+                    &scope,
+                    &item_fn,
+                    fns,
+                    structs,
+                    fn_output_names,
+                    crate_context,
+                );
+                data.reachable =
+                    is_mod_reachable && matches!(trait_item.get_visibility(), MyVisibility::PubT);
+                write_syntax_data(
+                    output_path,
+                    &(mod_tree.clone()
+                        + "::"
+                        + &function_item.get_complete_function_name_in_file()),
+                    &data,
+                );
+            }
+        }
+    }
+
     pub fn get_context(
         &self,
         output_path: &PathBuf,
         mod_tree: &String,
         mod_trees: &Vec<String>,
-        fns: &HashMap<String, FnData>,
-        structs: &HashMap<String, StructData>,
+        fns: &BTreeMap<String, FnData>,
+        structs: &BTreeMap<String, StructData>,
         crate_context: &CrateContext,
     ) {
         for function_item in self.functions.iter() {
             let complete_function_name =
                 mod_tree.clone() + "::" + &function_item.get_complete_function_name_in_file();
-            let call_file = output_path
-                .join(String::from("callsandtypes/") + &complete_function_name + ".json");
+            let call_file = output_path.join(
+                String::from("callsandtypes/")
+                    + &get_output_file_name(&complete_function_name)
+                    + ".json",
+            );
             // println!("{}", call_file.to_string_lossy());
             let mut file = File::open(call_file);
             match file {
@@ -1570,20 +3847,32 @@ impl SyntaxContext {
                     let mut syntax_context = SyntaxContext::new();
                     // syntax_context.functions.push(function_item.clone());
                     data.calls.push(function_item.get_complete_name());
-                    parse_callsandtypes(&mut data, mod_trees, &mut syntax_context, fns, structs);
-                    let rs_file_name = complete_function_name.clone() + ".rs";
-                    let output_file_path = output_path.join(rs_file_name);
-                    let mut file = File::create(output_file_path).unwrap();
-                    file.write_all(syntax_context.to_string().as_bytes())
-                        .unwrap();
-
-                    let directory_path = output_path.join("new_callsandtypes");
-                    create_dir_all(&directory_path).unwrap();
-                    let file_path = PathBuf::from(&directory_path)
-                        .join(format!("{}.json", complete_function_name.clone()));
-                    let mut file = File::create(&file_path).unwrap();
-                    file.write_all(serde_json::to_string(&data).unwrap().as_bytes())
-                        .unwrap();
+                    add_relative_types(
+                        &mut data,
+                        Vec::new(),
+                        &function_item.get_item().sig,
+                        mod_tree,
+                        crate_context.get_config().get_type_depth(),
+                        mod_trees,
+                        structs,
+                    );
+                    parse_callsandtypes(
+                        &mut data,
+                        mod_trees,
+                        &mut syntax_context,
+                        fns,
+                        structs,
+                        crate_context.get_config().get_trait_default_methods(),
+                    );
+                    write_context(
+                        output_path,
+                        &complete_function_name,
+                        mod_tree,
+                        &FnType::Fn(function_item.clone()),
+                        &data,
+                        &mut syntax_context,
+                        crate_context,
+                    );
                 }
                 Err(_) => {}
             }
@@ -1592,8 +3881,11 @@ impl SyntaxContext {
             for function_item in impl_item.get_fns().iter() {
                 let complete_function_name =
                     mod_tree.clone() + "::" + &function_item.get_complete_function_name_in_file();
-                let call_file = output_path
-                    .join(String::from("callsandtypes/") + &complete_function_name + ".json");
+                let call_file = output_path.join(
+                    String::from("callsandtypes/")
+                        + &get_output_file_name(&complete_function_name)
+                        + ".json",
+                );
                 let mut file = File::open(call_file);
                 match file {
                     Ok(mut file) => {
@@ -1604,14 +3896,22 @@ impl SyntaxContext {
                         data.calls.push(function_item.get_complete_name());
                         data.types
                             .push(impl_item.get_struct_name().get_import_name().to_string());
-                        let mut relative_types: Vec<String> = Vec::new();
-                        crate_context.get_relative_types_for_struct(
-                            &impl_item.get_struct_name().get_import_name().to_string(),
-                            &mut relative_types,
-                        );
-                        for relative_type in relative_types.iter() {
-                            data.types.push(relative_type.clone());
+                        let mut path_names: Vec<String> = Vec::new();
+                        impl_item.get_self_type().get_path_names(&mut path_names);
+                        for path_name in path_names {
+                            if !data.types.contains(&path_name) {
+                                data.types.push(path_name);
+                            }
                         }
+                        add_relative_types(
+                            &mut data,
+                            vec![impl_item.get_struct_name().get_import_name().to_string()],
+                            &function_item.get_item().sig,
+                            mod_tree,
+                            crate_context.get_config().get_type_depth(),
+                            mod_trees,
+                            structs,
+                        );
                         if let Some(trait_name) = impl_item.get_trait_name() {
                             data.types.push(trait_name.get_import_name().to_string());
                         }
@@ -1628,20 +3928,17 @@ impl SyntaxContext {
                             &mut syntax_context,
                             fns,
                             structs,
+                            crate_context.get_config().get_trait_default_methods(),
+                        );
+                        write_context(
+                            output_path,
+                            &complete_function_name,
+                            mod_tree,
+                            &FnType::ImplFn(function_item.clone(), impl_item.clone()),
+                            &data,
+                            &mut syntax_context,
+                            crate_context,
                         );
-                        let rs_file_name = complete_function_name.clone() + ".rs";
-                        let output_file_path = output_path.join(rs_file_name);
-                        let mut file = File::create(output_file_path).unwrap();
-                        file.write_all(syntax_context.to_string().as_bytes())
-                            .unwrap();
-
-                        let directory_path = output_path.join("new_callsandtypes");
-                        create_dir_all(&directory_path).unwrap();
-                        let file_path = PathBuf::from(&directory_path)
-                            .join(format!("{}.json", complete_function_name.clone()));
-                        let mut file = File::create(&file_path).unwrap();
-                        file.write_all(serde_json::to_string(&data).unwrap().as_bytes())
-                            .unwrap();
                         // exit(1);
                     }
                     Err(_) => {}
@@ -1652,8 +3949,11 @@ impl SyntaxContext {
             for function_item in trait_item.get_fns().iter() {
                 let complete_function_name =
                     mod_tree.clone() + "::" + &function_item.get_complete_function_name_in_file();
-                let call_file = output_path
-                    .join(String::from("callsandtypes/") + &complete_function_name + ".json");
+                let call_file = output_path.join(
+                    String::from("callsandtypes/")
+                        + &get_output_file_name(&complete_function_name)
+                        + ".json",
+                );
                 let mut file = File::open(call_file);
                 match file {
                     Ok(mut file) => {
@@ -1663,26 +3963,32 @@ impl SyntaxContext {
                         let mut syntax_context = SyntaxContext::new();
                         data.calls.push(function_item.get_complete_name());
                         data.types.push(trait_item.get_name());
+                        add_relative_types(
+                            &mut data,
+                            Vec::new(),
+                            &function_item.get_item().sig,
+                            mod_tree,
+                            crate_context.get_config().get_type_depth(),
+                            mod_trees,
+                            structs,
+                        );
                         parse_callsandtypes(
                             &mut data,
                             mod_trees,
                             &mut syntax_context,
                             fns,
                             structs,
+                            crate_context.get_config().get_trait_default_methods(),
+                        );
+                        write_context(
+                            output_path,
+                            &complete_function_name,
+                            mod_tree,
+                            &FnType::TraitFn(function_item.clone(), trait_item.clone()),
+                            &data,
+                            &mut syntax_context,
+                            crate_context,
                         );
-                        let rs_file_name = complete_function_name.clone() + ".rs";
-                        let output_file_path = output_path.join(rs_file_name);
-                        let mut file = File::create(output_file_path).unwrap();
-                        file.write_all(syntax_context.to_string().as_bytes())
-                            .unwrap();
-
-                        let directory_path = output_path.join("new_callsandtypes");
-                        create_dir_all(&directory_path).unwrap();
-                        let file_path = PathBuf::from(&directory_path)
-                            .join(format!("{}.json", complete_function_name.clone()));
-                        let mut file = File::create(&file_path).unwrap();
-                        file.write_all(serde_json::to_string(&data).unwrap().as_bytes())
-                            .unwrap();
                     }
                     Err(_) => {}
                 }
@@ -1690,31 +3996,676 @@ impl SyntaxContext {
         }
     }
 
-    fn to_string(&self) -> String {
-        let mut items: Vec<Item> = Vec::new();
-        items.extend(self.types.iter().map(|type_item| type_item.to_item()));
-        items.extend(self.uses.iter().map(|use_item| use_item.to_item()));
-        items.extend(self.mods.iter().map(|mod_item| mod_item.to_item()));
-        items.extend(self.statics.iter().map(|static_item| static_item.to_item()));
-        items.extend(self.consts.iter().map(|const_item| const_item.to_item()));
+    fn get_doctests(&self, doctests: &mut Vec<(String, Doctest)>) {
+        for function_item in self.functions.iter() {
+            push_doctests(
+                &function_item.get_complete_name(),
+                &function_item.get_item().attrs,
+                doctests,
+            );
+        }
+        for impl_item in self.impls.iter() {
+            let struct_name = impl_item.get_struct_name().get_import_name().to_string();
+            let impl_name = match impl_item.get_trait_name() {
+                Some(trait_name) => format!(
+                    "impl {} for {}",
+                    trait_name.get_import_name().to_string(),
+                    struct_name
+                ),
+                None => format!("impl {}", struct_name),
+            };
+            push_doctests(&impl_name, &impl_item.get_item().attrs, doctests);
+            for impl_fn_item in impl_item.get_fns().iter() {
+                push_doctests(
+                    &impl_fn_item.get_complete_name(),
+                    &impl_fn_item.get_item().attrs,
+                    doctests,
+                );
+            }
+        }
+        for trait_item in self.traits.iter() {
+            push_doctests(
+                &trait_item.get_trait_name().get_import_name().to_string(),
+                &get_item_attrs(&trait_item.to_item()),
+                doctests,
+            );
+            for trait_fn_item in trait_item.get_fns().iter() {
+                push_doctests(
+                    &trait_fn_item.get_complete_name(),
+                    &trait_fn_item.get_item().attrs,
+                    doctests,
+                );
+            }
+        }
+        for struct_item in self.structs.iter() {
+            push_doctests(
+                &struct_item.get_struct_name().get_import_name().to_string(),
+                &get_item_attrs(&struct_item.to_item()),
+                doctests,
+            );
+        }
+        for enum_item in self.enums.iter() {
+            push_doctests(
+                &enum_item.get_enum_name().get_import_name().to_string(),
+                &get_item_attrs(&enum_item.to_item()),
+                doctests,
+            );
+        }
+        for union_item in self.unions.iter() {
+            push_doctests(
+                &union_item.get_union_name().get_import_name().to_string(),
+                &get_item_attrs(&union_item.to_item()),
+                doctests,
+            );
+        }
+        for type_item in self.types.iter() {
+            if let Item::Type(item_type) = type_item.to_item() {
+                push_doctests(&item_type.ident.to_string(), &item_type.attrs, doctests);
+            }
+        }
+    }
+
+    fn to_string(
+        &self,
+        keep_docs: &KeepDocs,
+        focal_name: &String,
+        crate_context: &CrateContext,
+    ) -> String {
+        // every item is kept with the mod tree it is defined in
+        let crate_name = crate_context.get_crate_name();
+        let mut items: Vec<(String, Item)> = Vec::new();
+        items.extend(
+            self.types
+                .iter()
+                .map(|type_item| (type_item.get_mod_tree().to_string(), type_item.to_item())),
+        );
         items.extend(
-            self.trait_aliases
+            self.uses
                 .iter()
-                .map(|trait_alias_item| trait_alias_item.to_item()),
+                .map(|use_item| (use_item.get_mod_tree().to_string(), use_item.to_item())),
         );
-        items.extend(self.traits.iter().map(|trait_item| trait_item.to_item()));
-        items.extend(self.structs.iter().map(|struct_item| struct_item.to_item()));
-        items.extend(self.enums.iter().map(|enum_item| enum_item.to_item()));
-        items.extend(self.unions.iter().map(|union_item| union_item.to_item()));
-        items.extend(self.impls.iter().map(|impl_item| impl_item.to_item()));
         items.extend(
-            self.functions
+            self.mods
                 .iter()
-                .map(|function_item| function_item.to_item()),
+                .map(|mod_item| (mod_item.get_mod_tree().to_string(), mod_item.to_item())),
+        );
+        items.extend(self.statics.iter().map(|static_item| {
+            (
+                static_item.get_mod_tree().to_string(),
+                static_item.to_item(),
+            )
+        }));
+        items.extend(
+            self.consts
+                .iter()
+                .map(|const_item| (const_item.get_mod_tree().to_string(), const_item.to_item())),
+        );
+        items.extend(self.trait_aliases.iter().map(|trait_alias_item| {
+            (
+                trait_alias_item.get_mod_tree().to_string(),
+                trait_alias_item.to_item(),
+            )
+        }));
+        // the `macro_rules!` of an invoked macro comes right before the first invocation
+        let mut macro_rules: Vec<&ItemMacro> = Vec::new();
+        for macro_item in self.macros.iter() {
+            let mod_tree = macro_item.get_mod_tree().to_string();
+            if let Some(rules) = macro_item.get_rules() {
+                if !macro_rules.contains(&rules) {
+                    macro_rules.push(rules);
+                    items.push((mod_tree.clone(), Item::Macro(rules.clone())));
+                }
+            }
+            items.push((mod_tree, macro_item.to_item()));
+        }
+        items.extend(self.foreign_mods.iter().map(|foreign_mod_item| {
+            (
+                foreign_mod_item.get_mod_tree().to_string(),
+                foreign_mod_item.to_item(),
+            )
+        }));
+        if !matches!(keep_docs, KeepDocs::All) {
+            for (_, item) in items.iter_mut() {
+                delete_item_doc_attributes(item);
+            }
+        }
+        items.extend(self.traits.iter().map(|trait_item| {
+            (
+                get_parent_mod(&trait_item.get_trait_name().get_import_name().to_string()),
+                trait_to_item_with_docs(trait_item, keep_docs, focal_name),
+            )
+        }));
+        let mut data_items: Vec<(String, Item)> = Vec::new();
+        data_items.extend(self.structs.iter().map(|struct_item| {
+            (
+                get_parent_mod(&struct_item.get_struct_name().get_import_name().to_string()),
+                struct_item.to_item(),
+            )
+        }));
+        data_items.extend(self.enums.iter().map(|enum_item| {
+            (
+                get_parent_mod(&enum_item.get_enum_name().get_import_name().to_string()),
+                enum_item.to_item(),
+            )
+        }));
+        data_items.extend(self.unions.iter().map(|union_item| {
+            (
+                get_parent_mod(&union_item.get_union_name().get_import_name().to_string()),
+                union_item.to_item(),
+            )
+        }));
+        if !matches!(keep_docs, KeepDocs::All) {
+            for (_, item) in data_items.iter_mut() {
+                delete_item_doc_attributes(item);
+            }
+        }
+        items.extend(data_items);
+        items.extend(self.impls.iter().map(|impl_item| {
+            (
+                impl_item.get_mod_tree().to_string(),
+                impl_to_item_with_docs(impl_item, keep_docs, focal_name),
+            )
+        }));
+        for function_item in self.functions.iter() {
+            let mut item = function_item.to_item();
+            if !keep_fn_docs(keep_docs, &function_item.get_complete_name(), focal_name) {
+                delete_item_doc_attributes(&mut item);
+            }
+            items.push((get_parent_mod(&function_item.get_complete_name()), item));
+        }
+        // every item is led by the place it was taken from
+        let mut item_texts: Vec<(String, String)> = Vec::new();
+        for (mod_tree, item) in items {
+            let mut text = String::new();
+            if let Some(source_location) = crate_context.get_source_location(&item) {
+                text += &(source_location.to_comment() + "\n");
+            }
+            // macro bodies are only tokens to `unparse`, they are kept as written
+            let verbatim_text = match (crate_context.get_config().get_render(), &item) {
+                (Render::Verbatim, _) | (Render::Pretty, Item::Macro(_)) => {
+                    get_verbatim_item(&item)
+                }
+                (Render::Pretty, _) => None,
+            };
+            match verbatim_text {
+                Some(verbatim_text) => text += &verbatim_text,
+                None => {
+                    text += &unparse(&syn::File {
+                        shebang: None,
+                        attrs: Vec::new(),
+                        items: vec![item],
+                    });
+                }
+            }
+            item_texts.push((mod_tree, text));
+        }
+        if !crate_context.get_config().get_nest_modules() {
+            let mut context = String::new();
+            for (_, text) in item_texts.iter() {
+                context += text;
+            }
+            return context;
+        }
+        for (mod_tree, _) in item_texts.iter_mut() {
+            if *mod_tree != crate_name && !mod_tree.starts_with(&(crate_name.clone() + "::")) {
+                *mod_tree = crate_name.clone();
+            }
+        }
+        nest_item_texts(&crate_name, &item_texts, crate_context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use call_chain::analysis::sourceinfo::SourceInfo;
+    use std::{env::temp_dir, fs};
+    use syn::{parse_file, parse_str};
+
+    fn get_crate_context(dir_name: &str, lib_code: &str) -> CrateContext {
+        let crate_path = temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&crate_path);
+        fs::create_dir_all(crate_path.join("src")).unwrap();
+        fs::write(
+            crate_path.join("Cargo.toml"),
+            "[package]\nname = \"sample\"\n",
+        )
+        .unwrap();
+        fs::write(crate_path.join("src/lib.rs"), lib_code).unwrap();
+        let mut crate_context = CrateContext::new(&crate_path);
+        crate_context.parse_crate();
+        crate_context.change_all_names();
+        crate_context
+    }
+
+    // the context of a fn in the crate root that uses the items of `code`
+    fn get_root_context(code: &str) -> SyntaxContext {
+        let items = parse_file(code).unwrap().items;
+        let mut syntax_context = SyntaxContext::from_items(&items);
+        syntax_context.change_fn_struct_enum_union_trait_name(&"sample".to_string());
+        syntax_context
+    }
+
+    fn get_visibility(code: &str) -> MyVisibility {
+        parse_visibility(&parse_str::<Visibility>(code).unwrap())
+    }
+
+    #[test]
+    fn visibility_scopes_are_resolved_from_the_defining_mod() {
+        let def_mod = "sample::shapes::inner";
+        let get_scope = |code: &str| get_visibility_scope(&get_visibility(code), def_mod, "sample");
+        assert_eq!(get_scope("pub"), None);
+        assert_eq!(get_scope("pub(crate)"), Some("sample".to_string()));
+        assert_eq!(get_scope(""), Some("sample::shapes::inner".to_string()));
+        assert_eq!(
+            get_scope("pub(self)"),
+            Some("sample::shapes::inner".to_string())
+        );
+        assert_eq!(get_scope("pub(super)"), Some("sample::shapes".to_string()));
+        assert_eq!(
+            get_scope("pub(in crate::shapes)"),
+            Some("sample::shapes".to_string())
+        );
+        assert_eq!(
+            get_scope("pub(in super::super)"),
+            Some("sample".to_string())
+        );
+    }
+
+    #[test]
+    fn items_are_visible_from_tests_inside_their_scope() {
+        let visibility = get_visibility("pub(super)");
+        let is_visible = |test_mod: Option<&str>| {
+            is_visible_from(
+                &visibility,
+                "sample::shapes::inner",
+                "sample",
+                &test_mod.map(String::from),
+            )
+        };
+        assert!(is_visible(Some("sample::shapes::tests")));
+        assert!(is_visible(Some("sample::shapes")));
+        // a mod whose name only starts with the scope is outside of it
+        assert!(!is_visible(Some("sample::shapes_extra::tests")));
+        assert!(!is_visible(Some("sample::tests")));
+        // integration tests see only `pub` items
+        assert!(!is_visible(None));
+        assert!(is_visible_from(
+            &get_visibility("pub"),
+            "sample::shapes::inner",
+            "sample",
+            &None
+        ));
+    }
+
+    #[test]
+    fn uses_consts_and_fns_are_nested_in_their_defining_mod() {
+        let mut crate_context = get_crate_context("rfocxt_nest_item_texts", "");
+        let mut config = crate_context.get_config().clone();
+        config.insert_nest_modules(true);
+        crate_context.insert_config(&config);
+        let items = parse_file(
+            "use std::fmt::Debug;
+            const LIMIT: usize = 8;
+            fn under_limit(x: usize) -> bool { x < LIMIT }",
+        )
+        .unwrap()
+        .items;
+        let mut syntax_context = SyntaxContext::from_items(&items);
+        syntax_context.change_fn_struct_enum_union_trait_name(&"sample::child".to_string());
+        assert_eq!(
+            syntax_context.uses[0].get_mod_tree().to_string(),
+            "sample::child"
+        );
+        assert_eq!(
+            syntax_context.consts[0].get_mod_tree().to_string(),
+            "sample::child"
+        );
+        let context = syntax_context.to_string(
+            &KeepDocs::None,
+            &"sample::child::under_limit".to_string(),
+            &crate_context,
+        );
+        assert!(context.starts_with("mod child {\n"));
+        assert!(context.ends_with("}\n"));
+        for line in [
+            "use std::fmt::Debug;",
+            "const LIMIT: usize = 8;",
+            "fn under_limit(",
+        ] {
+            assert!(
+                context.contains(&("    ".to_string() + line)),
+                "`{}` is not inside `mod child`:\n{}",
+                line,
+                context
+            );
+        }
+    }
+
+    #[test]
+    fn existing_tests_are_wrapped_in_a_test_module() {
+        let tests = vec![
+            "#[test]\nfn adds() {\n    assert_eq!(add(1, 2), 3);\n}\n".to_string(),
+            "#[test]\nfn adds_zero() {\n    assert_eq!(add(0, 2), 2);\n}".to_string(),
+        ];
+        let mut out = String::new();
+        SyntaxContext::new().append_tests(&mut out, "sample::add", &tests);
+        assert_eq!(
+            out,
+            "\n// Existing tests that call `sample::add`:\n\
+             #[cfg(test)]\n\
+             mod existing_tests {\n    use super::*;\n\
+             \n    #[test]\n    fn adds() {\n        assert_eq!(add(1, 2), 3);\n    }\n\
+             \n    #[test]\n    fn adds_zero() {\n        assert_eq!(add(0, 2), 2);\n    }\n\
+             }\n"
+        );
+        let mut out = String::new();
+        SyntaxContext::new().append_tests(&mut out, "sample::add", &[]);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn context_types_bring_their_impls_and_trait_summary() {
+        let point = "#[derive(Debug, Clone, PartialEq)]
+            pub struct Point {
+                pub x: i32,
+            }";
+        let impls = "impl Point {
+                pub fn new(x: i32) -> Self {
+                    Point { x }
+                }
+            }
+            impl Default for Point {
+                fn default() -> Self {
+                    Point::new(0)
+                }
+            }";
+        let crate_context = get_crate_context("rfocxt_trait_summary", &(point.to_string() + impls));
+        let mut syntax_context = get_root_context(point);
+        assert_eq!(
+            syntax_context.get_trait_summary(&crate_context),
+            vec![
+                "`sample::Point` derives: Debug, Clone, PartialEq".to_string(),
+                "`sample::Point` implements: Default".to_string(),
+            ]
+        );
+        syntax_context.add_impls_of_types(&crate_context);
+        assert_eq!(syntax_context.impls.len(), 2);
+        for impl_item in syntax_context.impls.iter() {
+            // the bodies are reduced to signatures
+            let text = unparse(&syn::File {
+                shebang: None,
+                attrs: Vec::new(),
+                items: vec![impl_item.to_item()],
+            });
+            assert!(!text.contains("Point { x }") && !text.contains("Point::new(0)"));
+        }
+    }
+
+    #[test]
+    fn new_calls_and_types_are_added_in_sorted_order() {
+        let get_data = |calls: &[&str], types: &[&str]| {
+            let mut data = CallsAndTypes::new(
+                "sample",
+                true,
+                &SourceInfo::new("src/lib.rs", 1, 0, 1, 0),
+                &calls.iter().map(|call| call.to_string()).collect(),
+                &types.iter().map(|a_type| a_type.to_string()).collect(),
+            );
+            let mod_trees = vec!["sample".to_string(), "sample::shapes".to_string()];
+            add_new_calls_and_types(&mut data, &mod_trees);
+            data
+        };
+        let data = get_data(&["shapes::area", "area"], &["Circle"]);
+        assert_eq!(
+            data.calls,
+            vec![
+                "area",
+                "shapes::area",
+                "sample::area",
+                "sample::shapes::area",
+                "sample::shapes::shapes::area",
+            ]
+        );
+        assert_eq!(
+            data.types,
+            vec!["Circle", "sample::Circle", "sample::shapes::Circle"]
+        );
+        // the order does not depend on the order the calls were found in
+        assert_eq!(
+            get_data(&["area", "shapes::area"], &["Circle"]).calls,
+            data.calls
+        );
+    }
+
+    #[test]
+    fn mir_impl_calls_are_split_and_shortened() {
+        let split = |call: &str| split_impl_call(call).unwrap();
+        assert_eq!(
+            split("<(f64, f64) as shapes::Shape>::area"),
+            (
+                "(f64, f64)".to_string(),
+                Some("shapes::Shape".to_string()),
+                "area".to_string()
+            )
+        );
+        assert_eq!(
+            split("<[u8]>::first_byte"),
+            ("[u8]".to_string(), None, "first_byte".to_string())
+        );
+        assert_eq!(
+            split("shapes::<impl &'a Circle>::radius"),
+            ("&'a Circle".to_string(), None, "radius".to_string())
+        );
+        assert_eq!(split_impl_call("shapes::area"), None);
+        assert_eq!(
+            get_short_type_string("&'a std::vec::Vec<shapes::Circle>"),
+            "&Vec<Circle>"
+        );
+        assert_eq!(
+            get_short_type_string("(dyn shapes::Shape + 'static)"),
+            "dyn Shape"
+        );
+        // the impl side is written the same way as the MIR side
+        let self_type = |code: &str| {
+            SelfType::new(&parse_str::<Type>(code).unwrap(), &Generics::default())
+                .get_short_type_string()
+        };
+        assert_eq!(self_type("&'a Vec<crate::shapes::Circle>"), "&Vec<Circle>");
+        assert_eq!(self_type("dyn Shape"), "dyn Shape");
+        assert_eq!(self_type("(f64, f64)"), get_short_type_string("(f64, f64)"));
+    }
+
+    #[test]
+    fn impl_calls_pick_the_impl_their_self_type_binds_to() {
+        let crate_context = get_crate_context(
+            "rfocxt_impl_calls",
+            "pub struct Wrapper<T>(pub T);
+impl Wrapper<u8> {
+    pub fn get(&self) -> u8 { self.0 }
+}
+impl Wrapper<u16> {
+    pub fn get(&self) -> u16 { self.0 }
+}
+impl<T: Clone> Wrapper<T> {
+    pub fn get(&self) -> T { self.0.clone() }
+}
+",
+        );
+        let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+        let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
+        crate_context.get_result(&mut fns, &mut structs);
+        let impl_call = |self_type: &str| ImplCall {
+            call: format!("Wrapper::<{}>::get", self_type),
+            callee: "sample::Wrapper::get".to_string(),
+            self_type: format!("Wrapper<{}>", self_type),
+            generic_args: vec![self_type.to_string()],
+        };
+        let get_impl_fn = |self_type: &str| {
+            get_impl_fn_for_impl_call(&impl_call(self_type), &fns).map(|name| name.as_str())
+        };
+        // the concrete impl binds no params, so it wins over the generic one
+        assert_eq!(get_impl_fn("u8"), Some("sample::Wrapper::<u8>::get"));
+        assert_eq!(get_impl_fn("u16"), Some("sample::Wrapper::<u16>::get"));
+        assert_eq!(get_impl_fn("String"), Some("sample::Wrapper::get"));
+        let (impl_fn_item, impl_item) = match &fns["sample::Wrapper::<u16>::get"].fn_type {
+            FnType::ImplFn(impl_fn_item, impl_item) => (impl_fn_item, impl_item),
+            _ => unreachable!(),
+        };
+        assert!(!is_impl_applied(
+            impl_fn_item,
+            impl_item,
+            &[impl_call("u8")]
+        ));
+        assert!(is_impl_applied(
+            impl_fn_item,
+            impl_item,
+            &[impl_call("u16")]
+        ));
+        assert!(is_impl_applied(impl_fn_item, impl_item, &[]));
+    }
+
+    #[test]
+    fn traits_bring_their_supertraits_and_used_fns() {
+        let crate_context = get_crate_context(
+            "rfocxt_complete_trait",
+            "pub trait Named {
+    fn name(&self) -> String;
+}
+pub trait Shape: Named {
+    type Unit;
+    fn area(&self) -> Self::Unit;
+    fn sides(&self) -> u32;
+    fn describe(&self) -> String {
+        format!(\"{} {}\", self.name(), self.area())
+    }
+    fn unused(&self) {}
+}
+pub struct Square(pub f64);
+impl Named for Square {
+    fn name(&self) -> String { \"square\".to_string() }
+}
+impl Shape for Square {
+    type Unit = f64;
+    fn area(&self) -> f64 { self.0 * self.0 }
+    fn sides(&self) -> u32 { 4 }
+}
+",
+        );
+        let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+        let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
+        crate_context.get_result(&mut fns, &mut structs);
+        let get_context = |trait_default_methods: bool| {
+            // the fn under test calls `Square::area`
+            let mut syntax_context = get_root_context("");
+            if let FnType::ImplFn(impl_fn_item, impl_item) =
+                &fns["<sample::Square as sample::Shape>::area"].fn_type
+            {
+                let mut impl_item = impl_item.clone();
+                impl_item.insert_function(impl_fn_item);
+                syntax_context.impls.push(impl_item);
+            }
+            syntax_context.complete_traits(&fns, &structs, trait_default_methods);
+            syntax_context
+        };
+        let get_items = |syntax_context: &SyntaxContext| {
+            let mut items: Vec<(String, Vec<String>)> = Vec::new();
+            for trait_item in syntax_context.traits.iter() {
+                items.push((
+                    trait_item.get_trait_name().get_import_name().to_string(),
+                    trait_item.get_fns().iter().map(|f| f.get_name()).collect(),
+                ));
+            }
+            for impl_item in syntax_context.impls.iter() {
+                items.push((
+                    get_impl_trait_import_name(impl_item).unwrap() + " impl",
+                    impl_item.get_fns().iter().map(|f| f.get_name()).collect(),
+                ));
+            }
+            items
+        };
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        // the trait keeps the fns used in the context, the impls implement the same fns
+        let syntax_context = get_context(false);
+        assert_eq!(
+            get_items(&syntax_context),
+            vec![
+                ("sample::Shape".to_string(), names(&["area"])),
+                ("sample::Named".to_string(), names(&[])),
+                ("sample::Shape impl".to_string(), names(&["area"])),
+                ("sample::Named impl".to_string(), names(&[])),
+            ]
+        );
+        assert!(syntax_context.traits[0].has_type_or_const(&"Unit".to_string()));
+        // the default bodies bring the fns they call, also from the supertraits
+        assert_eq!(
+            get_items(&get_context(true)),
+            vec![
+                (
+                    "sample::Shape".to_string(),
+                    names(&["area", "describe", "unused"])
+                ),
+                ("sample::Named".to_string(), names(&["name"])),
+                ("sample::Shape impl".to_string(), names(&["area"])),
+                ("sample::Named impl".to_string(), names(&["name"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_types_are_followed_type_depth_levels() {
+        let crate_context = get_crate_context(
+            "rfocxt_relative_types",
+            "pub struct Outer { pub mid: Mid }
+pub struct Mid { pub inner: inner::Inner, pub node: Node }
+pub struct Node { pub next: Option<Box<Node>> }
+pub mod inner {
+    pub struct Inner { pub leaf: Leaf }
+    pub struct Leaf;
+}
+",
+        );
+        let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+        let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
+        crate_context.get_result(&mut fns, &mut structs);
+        let mod_trees = vec!["sample".to_string(), "sample::inner".to_string()];
+        let sig = parse_str::<ItemFn>("fn measure(outer: Outer) {}")
+            .unwrap()
+            .sig;
+        let get_types = |type_depth: usize| {
+            let mut data = CallsAndTypes::new(
+                "sample",
+                true,
+                &SourceInfo::new("src/lib.rs", 1, 0, 1, 0),
+                &BTreeSet::new(),
+                &BTreeSet::new(),
+            );
+            add_relative_types(
+                &mut data,
+                Vec::new(),
+                &sig,
+                &"sample".to_string(),
+                type_depth,
+                &mod_trees,
+                &structs,
+            );
+            data.types
+        };
+        assert!(get_types(0).is_empty());
+        assert_eq!(get_types(1), vec!["sample::Mid"]);
+        assert_eq!(
+            get_types(2),
+            vec!["sample::Mid", "sample::inner::Inner", "sample::Node"]
+        );
+        // the recursive `Node` is followed once
+        assert_eq!(
+            get_types(5),
+            vec![
+                "sample::Mid",
+                "sample::inner::Inner",
+                "sample::Node",
+                "sample::inner::Leaf"
+            ]
         );
-        let tokens = quote! {#(#items)*};
-        let syntax: syn::File = parse2(tokens).unwrap();
-        unparse(&syntax)
-        // tokens.to_string()
     }
 }
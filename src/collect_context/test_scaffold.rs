@@ -0,0 +1,511 @@
+use prettyplease::unparse;
+use quote::{format_ident, quote};
+use syn::{
+    parse2, parse_str,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    Expr, FnArg, GenericParam, Generics, Pat, PathArguments, Signature, Stmt, Type,
+};
+
+use super::result::FnType;
+
+struct SelfTypeReplacer {
+    self_ty: Type,
+}
+
+impl VisitMut for SelfTypeReplacer {
+    fn visit_type_mut(&mut self, node: &mut Type) {
+        if let Type::Path(type_path) = node {
+            if type_path.qself.is_none() && type_path.path.is_ident("Self") {
+                *node = self.self_ty.clone();
+                return;
+            }
+        }
+        visit_mut::visit_type_mut(self, node);
+    }
+}
+
+struct GenericTypeVisitor {
+    generic_names: Vec<String>,
+    has_generic: bool,
+}
+
+impl<'ast> Visit<'ast> for GenericTypeVisitor {
+    fn visit_type(&mut self, node: &'ast Type) {
+        match node {
+            Type::ImplTrait(_) | Type::Infer(_) => {
+                self.has_generic = true;
+                return;
+            }
+            Type::Path(type_path) => {
+                let first = type_path.path.segments.first().unwrap().ident.to_string();
+                if type_path.qself.is_some()
+                    || first.eq("Self")
+                    || (type_path.path.segments.len() == 1 && self.generic_names.contains(&first))
+                {
+                    self.has_generic = true;
+                    return;
+                }
+            }
+            _ => {}
+        }
+        visit::visit_type(self, node);
+    }
+}
+
+fn get_generic_names(generics: &Generics, generic_names: &mut Vec<String>) {
+    for generic_param in generics.params.iter() {
+        match generic_param {
+            GenericParam::Type(type_param) => {
+                generic_names.push(type_param.ident.to_string());
+            }
+            GenericParam::Const(const_param) => {
+                generic_names.push(const_param.ident.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn has_generic(ty: &Type, generic_names: &Vec<String>) -> bool {
+    let mut visitor = GenericTypeVisitor {
+        generic_names: generic_names.clone(),
+        has_generic: false,
+    };
+    visitor.visit_type(ty);
+    visitor.has_generic
+}
+
+fn get_placeholder(ty: &Type) -> Expr {
+    let placeholder = match ty {
+        Type::Reference(type_reference) => match &*type_reference.elem {
+            Type::Path(type_path) if type_path.path.is_ident("str") => "\"\"",
+            Type::Slice(_) => {
+                if type_reference.mutability.is_some() {
+                    "&mut []"
+                } else {
+                    "&[]"
+                }
+            }
+            _ => "todo!()",
+        },
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let last = type_path.path.segments.last().unwrap();
+            let is_single = type_path.path.segments.len() == 1;
+            match last.ident.to_string().as_str() {
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "isize"
+                    if is_single =>
+                {
+                    "0"
+                }
+                "f32" | "f64" if is_single => "0.0",
+                "bool" if is_single => "false",
+                "char" if is_single => "'a'",
+                "String" => "String::new()",
+                "Vec" => "Vec::new()",
+                "Option" => "None",
+                _ => "todo!()",
+            }
+        }
+        Type::Tuple(type_tuple) if type_tuple.elems.is_empty() => "()",
+        _ => "todo!()",
+    };
+    parse_str(placeholder).unwrap()
+}
+
+fn get_type_path_without_arguments(ty: &Type) -> Option<syn::Path> {
+    if let Type::Path(type_path) = ty {
+        if type_path.qself.is_none() {
+            let mut path = type_path.path.clone();
+            for segment in path.segments.iter_mut() {
+                segment.arguments = PathArguments::None;
+            }
+            return Some(path);
+        }
+    }
+    None
+}
+
+fn in_crate_path(import_path: &String, crate_name: &String) -> String {
+    if import_path.eq(crate_name) {
+        "crate".to_string()
+    } else if import_path.starts_with(&(crate_name.clone() + "::")) {
+        "crate".to_string() + &import_path[crate_name.len()..]
+    } else {
+        import_path.clone()
+    }
+}
+
+fn get_arg_stmts(
+    sig: &Signature,
+    generic_names: &Vec<String>,
+    self_ty: &Option<Type>,
+    arg_stmts: &mut Vec<Stmt>,
+    arg_names: &mut Vec<syn::Ident>,
+) {
+    let mut num: usize = 0;
+    for fn_arg in sig.inputs.iter() {
+        if let FnArg::Typed(pat_type) = fn_arg {
+            let name = match &*pat_type.pat {
+                Pat::Ident(pat_ident) => pat_ident.ident.clone(),
+                _ => format_ident!("arg{}", num),
+            };
+            num += 1;
+            let mut ty = *pat_type.ty.clone();
+            if let Some(self_ty) = self_ty {
+                let mut replacer = SelfTypeReplacer {
+                    self_ty: self_ty.clone(),
+                };
+                replacer.visit_type_mut(&mut ty);
+            }
+            let placeholder = get_placeholder(&ty);
+            let stmt: Stmt = if has_generic(&ty, generic_names) {
+                parse2(quote! { let #name = #placeholder; }).unwrap()
+            } else {
+                parse2(quote! { let #name: #ty = #placeholder; }).unwrap()
+            };
+            arg_stmts.push(stmt);
+            arg_names.push(name);
+        }
+    }
+}
+
+// the paths an integration test imports the fn and the types it is called with from, by
+// the paths they are public at, `None` if one of them can not be named from outside
+pub fn get_integration_uses(
+    fn_type: &FnType,
+    crate_name: &str,
+    get_public_path: impl Fn(&str) -> Option<String>,
+) -> Option<Vec<String>> {
+    let mut def_paths: Vec<String> = Vec::new();
+    match fn_type {
+        FnType::Fn(fn_item) => {
+            def_paths.push(fn_item.get_complete_name());
+        }
+        FnType::ImplFn(_, impl_item) => {
+            let struct_import_name = impl_item.get_struct_name().get_import_name().to_string();
+            if struct_import_name.starts_with(&(crate_name.to_string() + "::")) {
+                def_paths.push(struct_import_name);
+            }
+            if let Some(trait_name) = impl_item.get_trait_name() {
+                let trait_import_name = trait_name.get_import_name().to_string();
+                if trait_import_name.starts_with(&(crate_name.to_string() + "::")) {
+                    def_paths.push(trait_import_name);
+                }
+            }
+        }
+        FnType::TraitFn(_, trait_item) => {
+            def_paths.push(trait_item.get_trait_name().get_import_name().to_string());
+        }
+        FnType::ForeignFn(_, _) => return None,
+    }
+    def_paths
+        .iter()
+        .map(|def_path| get_public_path(def_path))
+        .collect()
+}
+
+// an integration test is written if the uses from `get_integration_uses` are given, else a
+// unit test module, a fn whose path is not a valid syn path, e.g. with `{impl#0}` or a raw
+// ident, gets no integration test
+pub fn get_test_scaffold(
+    fn_type: &FnType,
+    mod_tree: &String,
+    crate_name: &String,
+    integration_uses: &Option<Vec<String>>,
+) -> Option<String> {
+    let reachable = integration_uses.is_some();
+    let mut generic_names: Vec<String> = Vec::new();
+    let mut arg_stmts: Vec<Stmt> = Vec::new();
+    let mut arg_names: Vec<syn::Ident> = Vec::new();
+    let mut uses: Vec<syn::Path> = Vec::new();
+    let complete_fn_name: String;
+    let sig: Signature;
+    let tokens;
+    match fn_type {
+        FnType::Fn(fn_item) => {
+            sig = fn_item.get_item().sig;
+            complete_fn_name = fn_item.get_complete_name();
+            get_generic_names(&sig.generics, &mut generic_names);
+            get_arg_stmts(&sig, &generic_names, &None, &mut arg_stmts, &mut arg_names);
+            let fn_name = &sig.ident;
+            let mut call = quote! { #fn_name(#(#arg_names),*) };
+            if sig.unsafety.is_some() {
+                call = quote! { unsafe { #call } };
+            }
+            let test_name = format_ident!("test_{}", fn_name);
+            tokens = quote! {
+                #[test]
+                fn #test_name() {
+                    #(#arg_stmts)*
+                    let _result = #call;
+                }
+            };
+        }
+        FnType::ImplFn(impl_fn_item, impl_item) => {
+            sig = impl_fn_item.get_item().sig;
+            complete_fn_name = impl_fn_item.get_complete_name();
+            let item_impl = impl_item.get_item();
+            let self_ty = *item_impl.self_ty.clone();
+            get_generic_names(&item_impl.generics, &mut generic_names);
+            get_generic_names(&sig.generics, &mut generic_names);
+            get_arg_stmts(
+                &sig,
+                &generic_names,
+                &Some(self_ty.clone()),
+                &mut arg_stmts,
+                &mut arg_names,
+            );
+            let fn_name = &sig.ident;
+            let mut call;
+            if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+                let (mut receiver_ty, mutable) = if let Some(_) = receiver.reference {
+                    (self_ty.clone(), receiver.mutability.is_some())
+                } else {
+                    (*receiver.ty.clone(), false)
+                };
+                let mut replacer = SelfTypeReplacer {
+                    self_ty: self_ty.clone(),
+                };
+                replacer.visit_type_mut(&mut receiver_ty);
+                let stmt: Stmt = match (has_generic(&receiver_ty, &generic_names), mutable) {
+                    (true, true) => parse2(quote! { let mut receiver = todo!(); }).unwrap(),
+                    (true, false) => parse2(quote! { let receiver = todo!(); }).unwrap(),
+                    (false, true) => {
+                        parse2(quote! { let mut receiver: #receiver_ty = todo!(); }).unwrap()
+                    }
+                    (false, false) => {
+                        parse2(quote! { let receiver: #receiver_ty = todo!(); }).unwrap()
+                    }
+                };
+                arg_stmts.insert(0, stmt);
+                call = quote! { receiver.#fn_name(#(#arg_names),*) };
+            } else if let Some(self_path) = get_type_path_without_arguments(&self_ty) {
+                call = quote! { #self_path::#fn_name(#(#arg_names),*) };
+            } else {
+                call = quote! { <#self_ty>::#fn_name(#(#arg_names),*) };
+            }
+            if sig.unsafety.is_some() {
+                call = quote! { unsafe { #call } };
+            }
+            if let Some(trait_name) = impl_item.get_trait_name() {
+                let trait_import_name = trait_name.get_import_name().to_string();
+                if !reachable && trait_import_name.starts_with(&(crate_name.clone() + "::")) {
+                    uses.push(parse_str(&in_crate_path(&trait_import_name, crate_name)).ok()?);
+                }
+            }
+            let test_name = format_ident!("test_{}", fn_name);
+            tokens = quote! {
+                #[test]
+                fn #test_name() {
+                    #(#arg_stmts)*
+                    let _result = #call;
+                }
+            };
+        }
+        FnType::TraitFn(trait_fn_item, trait_item) => {
+            sig = trait_fn_item.get_item().sig;
+            complete_fn_name = trait_fn_item.get_complete_name();
+            let trait_ident = &trait_item.get_item().ident;
+            let self_ty: Type = parse2(quote! { T }).unwrap();
+            generic_names.push("T".to_string());
+            get_generic_names(&sig.generics, &mut generic_names);
+            get_arg_stmts(
+                &sig,
+                &generic_names,
+                &Some(self_ty),
+                &mut arg_stmts,
+                &mut arg_names,
+            );
+            let fn_name = &sig.ident;
+            let helper_name = format_ident!("call_{}", fn_name);
+            let test_name = format_ident!("test_{}", fn_name);
+            let mut call;
+            let helper_args;
+            if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+                if receiver.reference.is_some() && receiver.mutability.is_some() {
+                    helper_args = quote! { mut receiver: T };
+                } else {
+                    helper_args = quote! { receiver: T };
+                }
+                call = quote! { receiver.#fn_name(#(#arg_names),*) };
+            } else {
+                helper_args = quote! {};
+                call = quote! { T::#fn_name(#(#arg_names),*) };
+            }
+            if sig.unsafety.is_some() {
+                call = quote! { unsafe { #call } };
+            }
+            let message = format!(
+                "call `{}` with a value of a type implementing `{}`",
+                helper_name, trait_ident
+            );
+            tokens = quote! {
+                #[allow(dead_code)]
+                fn #helper_name<T: #trait_ident>(#helper_args) {
+                    #(#arg_stmts)*
+                    let _result = #call;
+                }
+
+                #[test]
+                #[ignore]
+                fn #test_name() {
+                    todo!(#message);
+                }
+            };
+        }
+        // foreign fns have no body and get no context of their own, they are only
+        // scaffolded through the fns that call them
+        FnType::ForeignFn(_, _) => return None,
+    }
+    let mut scaffold = String::new();
+    let syntax: syn::File;
+    if let Some(integration_uses) = integration_uses {
+        for integration_use in integration_uses.iter() {
+            uses.push(parse_str(integration_use).ok()?);
+        }
+        scaffold += &format!("// Integration test scaffold for `{}`.\n", complete_fn_name);
+        scaffold += "// Place this file in the `tests` directory of the crate.\n";
+        syntax = parse2(quote! {
+            #(use #uses;)*
+
+            #tokens
+        })
+        .ok()?;
+    } else {
+        scaffold += &format!("// Unit test scaffold for `{}`.\n", complete_fn_name);
+        scaffold += &format!("// Place this module inside the module `{}`.\n", mod_tree);
+        syntax = parse2(quote! {
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+                #(use #uses;)*
+
+                #tokens
+            }
+        })
+        .ok()?;
+    }
+    scaffold += &unparse(&syntax);
+    Some(scaffold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collect_context::items_context::{FnItem, ForeignFnItem, ForeignModItem};
+
+    fn get_fn_type(mod_tree: &str, code: &str) -> FnType {
+        let item_fn: syn::ItemFn = parse_str(code).unwrap();
+        let mut fn_item = FnItem::new();
+        fn_item.insert_function_name(&item_fn.sig.ident.to_string());
+        fn_item.insert_parent_mod_tree(&mod_tree.to_string());
+        fn_item.insert_item(&item_fn);
+        FnType::Fn(fn_item)
+    }
+
+    fn get_def_path_uses(fn_type: &FnType) -> Option<Vec<String>> {
+        get_integration_uses(fn_type, "sample", |def_path| Some(def_path.to_string()))
+    }
+
+    #[test]
+    fn reachable_fns_get_an_integration_test() {
+        let fn_type = get_fn_type(
+            "sample::util",
+            "pub fn double(x: u32, v: impl Into<u8>) -> u32 { x }",
+        );
+        let scaffold = get_test_scaffold(
+            &fn_type,
+            &"sample::util".to_string(),
+            &"sample".to_string(),
+            &get_def_path_uses(&fn_type),
+        )
+        .unwrap();
+        assert!(scaffold.starts_with("// Integration test scaffold for `sample::util::double`."));
+        assert!(scaffold.contains("use sample::util::double;"));
+        assert!(!scaffold.contains("mod tests"));
+        assert!(scaffold.contains("let x: u32 = 0;"));
+        // an `impl Trait` arg has no type to write
+        assert!(scaffold.contains("let v = todo!();"));
+        assert!(scaffold.contains("let _result = double(x, v);"));
+    }
+
+    #[test]
+    fn private_fns_get_a_unit_test_module() {
+        let fn_type = get_fn_type("sample::util", "unsafe fn double(x: u32) -> u32 { x }");
+        let scaffold = get_test_scaffold(
+            &fn_type,
+            &"sample::util".to_string(),
+            &"sample".to_string(),
+            &None,
+        )
+        .unwrap();
+        assert!(scaffold.contains("// Place this module inside the module `sample::util`."));
+        assert!(scaffold.contains("#[cfg(test)]\nmod tests {\n    use super::*;"));
+        assert!(!scaffold.contains("use sample::util::double;"));
+        assert!(scaffold.contains("let _result = unsafe { double(x) };"));
+    }
+
+    #[test]
+    fn fns_without_a_valid_path_get_no_integration_test() {
+        let fn_type = get_fn_type("sample::{impl#0}", "pub fn double(x: u32) -> u32 { x }");
+        let crate_name = "sample".to_string();
+        let mod_tree = "sample".to_string();
+        let integration_uses = get_def_path_uses(&fn_type);
+        assert!(get_test_scaffold(&fn_type, &mod_tree, &crate_name, &integration_uses).is_none());
+        assert!(get_test_scaffold(&fn_type, &mod_tree, &crate_name, &None).is_some());
+    }
+
+    #[test]
+    fn re_exported_fns_are_imported_from_their_public_path() {
+        let fn_type = get_fn_type("sample::inner", "pub fn double(x: u32) -> u32 { x }");
+        let integration_uses = get_integration_uses(&fn_type, "sample", |def_path| {
+            def_path
+                .strip_prefix("sample::inner::")
+                .map(|name| "sample::".to_string() + name)
+        });
+        assert_eq!(integration_uses, Some(vec!["sample::double".to_string()]));
+        let scaffold = get_test_scaffold(
+            &fn_type,
+            &"sample::inner".to_string(),
+            &"sample".to_string(),
+            &integration_uses,
+        )
+        .unwrap();
+        assert!(scaffold.contains("use sample::double;"));
+        assert!(!scaffold.contains("use sample::inner"));
+    }
+
+    #[test]
+    fn fns_without_a_public_path_get_a_unit_test_module() {
+        let fn_type = get_fn_type("sample::inner", "pub fn double(x: u32) -> u32 { x }");
+        let integration_uses = get_integration_uses(&fn_type, "sample", |_| None);
+        assert_eq!(integration_uses, None);
+        let scaffold = get_test_scaffold(
+            &fn_type,
+            &"sample::inner".to_string(),
+            &"sample".to_string(),
+            &integration_uses,
+        )
+        .unwrap();
+        assert!(scaffold.starts_with("// Unit test scaffold for `sample::inner::double`."));
+        assert!(scaffold.contains("#[cfg(test)]\nmod tests {\n    use super::*;"));
+    }
+
+    #[test]
+    fn foreign_fns_get_no_scaffold() {
+        let mut foreign_fn_item = ForeignFnItem::new();
+        foreign_fn_item.insert_fn_name(&"getpid".to_string());
+        foreign_fn_item.insert_parent_mod_tree(&"sample::util".to_string());
+        foreign_fn_item.insert_item(&parse_str("fn getpid() -> i32;").unwrap());
+        let fn_type = FnType::ForeignFn(foreign_fn_item, ForeignModItem::new());
+        let crate_name = "sample".to_string();
+        let mod_tree = "sample::util".to_string();
+        assert!(get_integration_uses(&fn_type, &crate_name, |def_path| {
+            Some(def_path.to_string())
+        })
+        .is_none());
+        assert!(get_test_scaffold(&fn_type, &mod_tree, &crate_name, &None).is_none());
+    }
+}
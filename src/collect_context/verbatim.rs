@@ -0,0 +1,190 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{Block, ImplItem, Item, TraitItem};
+
+use super::items_context::get_cleared_block;
+
+// the span from the first to the last token, `None` if they aren't from the same parsed file
+fn get_source_span<T: ToTokens>(tokens: &T) -> Option<Span> {
+    let tokens = tokens.to_token_stream();
+    let first = tokens.clone().into_iter().next()?.span();
+    let last = tokens.into_iter().last()?.span();
+    first.join(last)
+}
+
+// the original text between two spans, with the indentation of the first line removed
+// from the following lines
+fn get_source_text(start: Span, end: Span) -> Option<String> {
+    let span = start.join(end)?;
+    let text = span.source_text()?;
+    let column = span.start().column;
+    let mut lines: Vec<String> = Vec::new();
+    for (index, line) in text.split('\n').enumerate() {
+        if index == 0 {
+            lines.push(line.to_string());
+            continue;
+        }
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        lines.push(line[indent.min(column)..].to_string());
+    }
+    Some(lines.join("\n"))
+}
+
+fn get_tokens_text<T: ToTokens>(tokens: &T) -> Option<String> {
+    let span = get_source_span(tokens)?;
+    get_source_text(span, span)
+}
+
+fn get_first_span<T: ToTokens>(tokens: &T) -> Option<Span> {
+    Some(tokens.to_token_stream().into_iter().next()?.span())
+}
+
+// bodies of fns in impls without method bodies are cleared, only the signature is kept
+fn is_cleared_block(block: &Block) -> bool {
+    if block.stmts != get_cleared_block().stmts {
+        return false;
+    }
+    match get_tokens_text(block) {
+        Some(text) => !text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .eq("{unimplemented!()}"),
+        None => false,
+    }
+}
+
+fn get_fn_text<T: ToTokens>(tokens: &T, block: &Block) -> Option<String> {
+    if is_cleared_block(block) {
+        let start = get_first_span(tokens)?;
+        let text = get_source_text(start, block.brace_token.span.open())?;
+        Some(text.trim_end_matches('{').trim_end().to_string() + " {\n    unimplemented!()\n}")
+    } else {
+        get_tokens_text(tokens)
+    }
+}
+
+pub fn indent(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        if line.is_empty() {
+            lines.push(String::new());
+        } else {
+            lines.push(String::from("    ") + line);
+        }
+    }
+    lines.join("\n")
+}
+
+// impls, traits and extern blocks are reassembled from their header and the members kept in the context
+fn get_block_item_text(
+    item: &Item,
+    brace_span: Span,
+    member_texts: Vec<Option<String>>,
+) -> Option<String> {
+    let header = get_source_text(get_first_span(item)?, brace_span)?;
+    let mut text = header + "\n";
+    for member_text in member_texts {
+        text += &(indent(&member_text?) + "\n");
+    }
+    Some(text + "}")
+}
+
+// the item as it is written in the crate, with comments and layout,
+// `None` if the item has no original text, e.g. rewritten uses
+pub fn get_verbatim_item(item: &Item) -> Option<String> {
+    let text = match item {
+        Item::Use(_) | Item::Mod(_) => return None,
+        Item::Fn(item_fn) => get_fn_text(item_fn, &item_fn.block)?,
+        Item::Impl(item_impl) => {
+            let mut member_texts: Vec<Option<String>> = Vec::new();
+            for member in item_impl.items.iter() {
+                member_texts.push(match member {
+                    ImplItem::Fn(impl_item_fn) => get_fn_text(impl_item_fn, &impl_item_fn.block),
+                    _ => get_tokens_text(member),
+                });
+            }
+            get_block_item_text(item, item_impl.brace_token.span.open(), member_texts)?
+        }
+        Item::Trait(item_trait) => {
+            let mut member_texts: Vec<Option<String>> = Vec::new();
+            for member in item_trait.items.iter() {
+                member_texts.push(match member {
+                    TraitItem::Fn(trait_item_fn) => match &trait_item_fn.default {
+                        Some(block) => get_fn_text(trait_item_fn, block),
+                        None => get_tokens_text(member),
+                    },
+                    _ => get_tokens_text(member),
+                });
+            }
+            get_block_item_text(item, item_trait.brace_token.span.open(), member_texts)?
+        }
+        Item::ForeignMod(item_foreign_mod) => {
+            let member_texts = item_foreign_mod.items.iter().map(get_tokens_text).collect();
+            get_block_item_text(item, item_foreign_mod.brace_token.span.open(), member_texts)?
+        }
+        _ => get_tokens_text(item)?,
+    };
+    Some(text + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_file;
+
+    #[test]
+    fn items_keep_their_comments_and_layout() {
+        let code =
+            "pub fn add(a: u8, b: u8) -> u8 {\n    // keep this comment\n    a + b  // and this\n}";
+        let file = parse_file(code).unwrap();
+        assert_eq!(
+            get_verbatim_item(&file.items[0]),
+            Some(code.to_string() + "\n")
+        );
+    }
+
+    #[test]
+    fn impls_keep_only_their_members_in_the_context() {
+        let code = "impl Foo {\n    const A: u8 = 1;\n\n    fn a(&self) -> u8 {\n        1\n    }\n\n    fn b(&self) -> u8 {\n        // two\n        2\n    }\n}";
+        let mut file = parse_file(code).unwrap();
+        if let Item::Impl(item_impl) = &mut file.items[0] {
+            item_impl.items.remove(1);
+        }
+        assert_eq!(
+            get_verbatim_item(&file.items[0]).unwrap(),
+            "impl Foo {\n    const A: u8 = 1;\n    fn b(&self) -> u8 {\n        // two\n        2\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn cleared_fns_keep_only_their_signature() {
+        let code = "impl Foo {\n    pub fn b(\n        &self,\n    ) -> u8 {\n        2\n    }\n}";
+        let mut file = parse_file(code).unwrap();
+        if let Item::Impl(item_impl) = &mut file.items[0] {
+            if let ImplItem::Fn(impl_item_fn) = &mut item_impl.items[0] {
+                impl_item_fn.block.stmts = get_cleared_block().stmts;
+            }
+        }
+        assert_eq!(
+            get_verbatim_item(&file.items[0]).unwrap(),
+            "impl Foo {\n    pub fn b(\n        &self,\n    ) -> u8 {\n        unimplemented!()\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn written_unimplemented_bodies_are_not_cleared() {
+        let code = "fn b() -> u8 { unimplemented!() }";
+        let file = parse_file(code).unwrap();
+        assert_eq!(
+            get_verbatim_item(&file.items[0]),
+            Some(code.to_string() + "\n")
+        );
+    }
+
+    #[test]
+    fn uses_have_no_verbatim_text() {
+        let file = parse_file("use std::fmt;").unwrap();
+        assert_eq!(get_verbatim_item(&file.items[0]), None);
+    }
+}
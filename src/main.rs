@@ -1,18 +1,22 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet},
     fs::{self, File},
     io::Write,
     path::PathBuf,
     process,
 };
 
+use check::check_all_contexts;
 use clap::Parser;
 use collect_context::{
+    config::{ContextConfig, KeepDocs, Render},
     crate_context::CrateContext,
     result::{FnData, StructData},
 };
+use serde_json::json;
 use utils::run_call_chain;
 
+mod check;
 mod collect_context;
 mod utils;
 
@@ -25,6 +29,56 @@ struct Cli {
     ///Sets crate path
     #[arg(short = 'c', long = "crate", required = true)]
     crate_path: String,
+
+    ///Checks that each generated context compiles
+    #[arg(long = "check")]
+    check: bool,
+
+    ///Sets the maximum number of existing tests included in each context
+    #[arg(long = "max-tests", default_value_t = 3)]
+    max_tests: usize,
+
+    ///Sets which items keep their doc comments
+    #[arg(long = "keep-docs", value_enum, default_value_t = KeepDocs::None)]
+    keep_docs: KeepDocs,
+
+    ///Sets how items are rendered, verbatim keeps the original comments and layout
+    #[arg(long = "render", value_enum, default_value_t = Render::Pretty)]
+    render: Render,
+
+    ///Includes every impl of the types in each context, with method bodies removed
+    #[arg(long = "include-impls")]
+    include_impls: bool,
+
+    ///Includes every default method of the traits in each context, with its body
+    #[arg(long = "trait-default-methods")]
+    trait_default_methods: bool,
+
+    ///Sets how many levels of nested field types are followed from the self and signature types
+    #[arg(long = "type-depth", default_value_t = 1)]
+    type_depth: usize,
+
+    ///Wraps the items of each context in the modules they are defined in
+    #[arg(long = "nest-modules")]
+    nest_modules: bool,
+
+    ///Builds the calls and types of each fn from its syntax instead of running call_chain,
+    ///weaker but it does not need the pinned nightly
+    #[arg(long = "syntax-only")]
+    syntax_only: bool,
+
+    ///Sets the lcov or llvm-cov JSON coverage report of the crate
+    #[arg(long = "coverage")]
+    coverage: Option<String>,
+
+    ///Only generates contexts for functions whose line coverage is below the percentage
+    #[arg(
+        long = "only-uncovered",
+        requires = "coverage",
+        num_args = 0..=1,
+        default_missing_value = "100"
+    )]
+    only_uncovered: Option<f64>,
 }
 
 fn main() {
@@ -34,14 +88,29 @@ fn main() {
         eprintln!("The crate path {:?} doesn't exisit!", &input_crate_path);
         process::exit(1)
     });
-    run_call_chain(&crate_path);
+    if !cli.syntax_only {
+        run_call_chain(&crate_path);
+    }
+
+    let mut config = ContextConfig::new();
+    config.insert_max_tests(cli.max_tests);
+    config.insert_keep_docs(cli.keep_docs);
+    config.insert_render(cli.render);
+    config.insert_include_impls(cli.include_impls);
+    config.insert_trait_default_methods(cli.trait_default_methods);
+    config.insert_type_depth(cli.type_depth);
+    config.insert_nest_modules(cli.nest_modules);
+    config.insert_only_uncovered(cli.only_uncovered);
+    config.insert_syntax_only(cli.syntax_only);
 
     let mut crate_context = CrateContext::new(&crate_path);
+    crate_context.insert_config(&config);
 
     crate_context.parse_crate();
     crate_context.change_all_names();
 
-    let mut mod_trees: HashSet<String> = HashSet::new();
+    // mod trees, fns and structs are kept sorted by name, every output is written in that order
+    let mut mod_trees: BTreeSet<String> = BTreeSet::new();
     crate_context.cout_all_mod_trees_in_on_file_for_test(&mut mod_trees);
     let mut mod_trees_vec: Vec<String> = Vec::new();
     for mod_tree in mod_trees.iter() {
@@ -49,20 +118,37 @@ fn main() {
     }
     let mod_trees = mod_trees_vec;
 
-    let mut fns: HashMap<String, FnData> = HashMap::new();
-    let mut structs: HashMap<String, StructData> = HashMap::new();
+    let mut fns: BTreeMap<String, FnData> = BTreeMap::new();
+    let mut structs: BTreeMap<String, StructData> = BTreeMap::new();
     crate_context.get_result(&mut fns, &mut structs);
+    if cli.syntax_only {
+        crate_context.get_syntax_macro_result(&mut structs);
+    }
     // println!("fns:\n{:#?}", fns);
     // println!("structs:\n{:#?}", structs);
-    let output_path = crate_path.join("rfocxt/result.txt");
+    let output_path = crate_path.join("rfocxt/result.json");
     fs::create_dir_all(output_path.parent().unwrap()).unwrap();
     let mut file = File::create(&output_path).unwrap();
-    file.write_all(format!("fns:\n{:#?}\n", fns).as_bytes())
-        .unwrap();
-    file.write_all(format!("structs:\n{:#?}", structs).as_bytes())
+    let result = json!({ "fns": fns, "structs": structs });
+    file.write_all(serde_json::to_string(&result).unwrap().as_bytes())
         .unwrap();
+    if cli.syntax_only {
+        crate_context.write_syntax_calls(&fns, &structs);
+    }
 
+    crate_context.collect_tests();
+    crate_context.collect_constructors(&structs);
+    if let Some(coverage_path) = &cli.coverage {
+        if let Err(message) = crate_context.collect_coverage(&PathBuf::from(coverage_path)) {
+            eprintln!("{}", message);
+            process::exit(6)
+        }
+    }
     crate_context.parse_all_context(&mod_trees, &fns, &structs);
     crate_context.cout_in_one_file_for_test();
     crate_context.cout_complete_function_name_in_on_file_for_test();
+
+    if cli.check {
+        check_all_contexts(&crate_path);
+    }
 }